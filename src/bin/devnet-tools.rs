@@ -0,0 +1,455 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use anyhow::bail;
+use akula_tools::convert::*;
+use akula_tools::devnet::*;
+use akula_tools::keystore::*;
+use akula_tools::models::chainspec::*;
+use akula_tools::p2p::*;
+use bytes::Bytes;
+use secp256k1::{PublicKey, SECP256K1};
+
+use clap::{Parser, Subcommand};
+
+#[derive(Parser)]
+#[clap(name = "Akula-tools", about = "tools to set up a bsc devnet running parlia.")]
+struct Opt {
+    #[clap(subcommand)]
+    pub cmd: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[clap(about = "derive validator account and BLS vote keys, and write them as keystores.")]
+    Keygen(KeygenOpt),
+    #[clap(about = "generate the geth genesis.json of a devnet and its akula chain spec.")]
+    Genesis(GenesisOpt),
+    #[clap(about = "lay out the node dirs of a local cluster, meshed by static nodes.")]
+    Cluster(ClusterOpt),
+    #[clap(about = "derive the enode URL of a node from its nodekey, or the bootnodes of a cluster.")]
+    Enode(EnodeOpt),
+    #[clap(about = "build and sign the EIP-1459 DNS discovery tree of a set of nodes as a zone file.")]
+    Dnstree(DnstreeOpt),
+    #[clap(about = "check the DNS discovery tree of a zone file against its enrtree:// URL.")]
+    Dnscheck(DnscheckOpt),
+    #[clap(about = "check the static and trusted peering of the nodes of a cluster.")]
+    Topology(TopologyOpt),
+}
+
+#[derive(Parser)]
+struct KeygenOpt {
+    #[clap(long, help = "number of validators.")]
+    pub count: u32,
+    #[clap(long, help = "mnemonic to derive the keys from, any phrase works.")]
+    pub mnemonic: Option<String>,
+    #[clap(long, help = "hex seed of 32 to 64 bytes to derive the keys from, instead of a mnemonic.")]
+    pub seed: Option<String>,
+    #[clap(long, help = "password of the keystores, derived from the seed for every validator by default.")]
+    pub password: Option<String>,
+    #[clap(long, help = "encrypt the keystores with geth's light scrypt parameters.")]
+    pub light_kdf: bool,
+    #[clap(long, help = "output path.")]
+    pub output: Option<String>,
+}
+
+/// Seed of the devnet keys, from a mnemonic or given in hex.
+fn parse_seed(mnemonic: &Option<String>, seed: &Option<String>) -> anyhow::Result<Vec<u8>> {
+    let seed = match (mnemonic, seed) {
+        (Some(mnemonic), None) => mnemonic_to_seed(mnemonic, "").to_vec(),
+        (None, Some(seed)) => hex::decode(seed.trim_start_matches("0x"))?,
+        _ => bail!("pass one of --mnemonic and --seed"),
+    };
+    if seed.len() < 32 || seed.len() > 64 {
+        bail!("seed must be 32 to 64 bytes long, got {}", seed.len());
+    }
+    Ok(seed)
+}
+
+fn keygen(opt: KeygenOpt) -> anyhow::Result<()> {
+    let seed = parse_seed(&opt.mnemonic, &opt.seed)?;
+    let scrypt = if opt.light_kdf { LIGHT_SCRYPT } else { STANDARD_SCRYPT };
+
+    let output = opt.output.unwrap_or(String::from("."));
+    let mut validators = Vec::new();
+    println!("{:<44} {}", "validator", "BLS public key");
+    for key in derive_validator_keys(&seed, opt.count)? {
+        let dir = Path::new(&output).join(format!("validator{}", key.index));
+        let info = write_validator_keys(&dir, &key, &seed, &opt.password, &scrypt)?;
+        println!("{:<44} {:?}", format!("{:?}", info.address), info.bls_pub_key);
+        validators.push(info);
+    }
+
+    let path = Path::new(&output).join("validators.ron");
+    fs::write(&path, ron::ser::to_string_pretty(&validators, ron::ser::PrettyConfig::new())?)?;
+    println!("{} validators saved in path: {:?}", validators.len(), &path.to_str());
+    Ok(())
+}
+
+#[derive(Parser)]
+struct GenesisOpt {
+    #[clap(long, help = "input the devnet .ron file location, with its forks, validators, balances and contracts.")]
+    pub config: String,
+    #[clap(long, help = "input a validators .ron file location, e.g. written by keygen, instead of the config's validators.")]
+    pub validators: Option<String>,
+    #[clap(long)]
+    pub name: Option<String>,
+    #[clap(long, help = "output path.")]
+    pub output: Option<String>,
+}
+
+fn genesis(opt: GenesisOpt) -> anyhow::Result<()> {
+    let mut config: DevnetConfig = ron::from_str(&fs::read_to_string(&opt.config)?)?;
+    if let Some(path) = &opt.validators {
+        config.validators = ron::from_str(&fs::read_to_string(path)?)?;
+    }
+
+    // contract files are relative to the config file
+    let base = Path::new(&opt.config).parent().unwrap_or_else(|| Path::new("."));
+    let mut contracts = BTreeMap::new();
+    for (address, file) in &config.contracts {
+        let code = fs::read_to_string(base.join(file))?;
+        contracts.insert(*address, Bytes::from(hex::decode(code.trim().trim_start_matches("0x"))?));
+    }
+    if let Some(path) = &config.contracts_from {
+        let spec: ChainSpec = ron::from_str(&fs::read_to_string(base.join(path))?)?;
+        for (address, contract) in spec.contracts.get(&spec.genesis.number).into_iter().flatten() {
+            if let Contract::Contract { code } = contract {
+                contracts.entry(*address).or_insert_with(|| code.clone());
+            }
+        }
+    }
+
+    let genesis = build_genesis(&config, &contracts)?;
+    let chain_spec = genesis_to_chain_spec(
+        &genesis,
+        opt.name.unwrap_or(String::from("BSC-devnet")),
+        P2PParams::default(),
+    )?;
+
+    let output = opt.output.unwrap_or(String::from("."));
+    let path = Path::new(&output).join("genesis.json");
+    fs::write(&path, serde_json::to_string_pretty(&genesis)?)?;
+    println!("geth's genesis saved in path: {:?}", &path.to_str());
+    let path = Path::new(&output).join(format!("{}.ron", chain_spec.name));
+    fs::write(&path, ron::ser::to_string_pretty(&chain_spec, ron::ser::PrettyConfig::new())?)?;
+    println!("akula's chain spec saved in path: {:?}", &path.to_str());
+    Ok(())
+}
+
+#[derive(Parser)]
+struct ClusterOpt {
+    #[clap(long, help = "input the chain spec .ron file location.")]
+    pub spec: String,
+    #[clap(long, help = "number of nodes.")]
+    pub nodes: u32,
+    #[clap(long, help = "mnemonic to derive the node and validator keys from, any phrase works.")]
+    pub mnemonic: Option<String>,
+    #[clap(long, help = "hex seed of 32 to 64 bytes to derive the keys from, instead of a mnemonic.")]
+    pub seed: Option<String>,
+    #[clap(long, help = "password of the keystores, derived from the seed for every node by default.")]
+    pub password: Option<String>,
+    #[clap(long, help = "encrypt the keystores with geth's light scrypt parameters.")]
+    pub light_kdf: bool,
+    #[clap(long, default_value = "127.0.0.1", help = "IP the nodes listen on.")]
+    pub ip: IpAddr,
+    #[clap(long, default_value = "30311", help = "p2p port of the first node, the next nodes listen on the next ports.")]
+    pub base_port: u16,
+    #[clap(long, help = "output path.")]
+    pub output: Option<String>,
+}
+
+fn cluster(opt: ClusterOpt) -> anyhow::Result<()> {
+    let mut chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(&opt.spec)?)?;
+    let signers = match &chain_spec.genesis.seal {
+        Seal::Parlia { signers, .. } => signers.clone(),
+        _ => vec![],
+    };
+    let seed = parse_seed(&opt.mnemonic, &opt.seed)?;
+    let scrypt = if opt.light_kdf { LIGHT_SCRYPT } else { STANDARD_SCRYPT };
+    let nodes = cluster_nodes(&seed, opt.nodes, opt.base_port)?;
+    let keys = derive_validator_keys(&seed, opt.nodes)?;
+
+    let output = opt.output.unwrap_or(String::from("."));
+    fs::create_dir_all(&output)?;
+    // geth resolves the paths in config.toml against its working dir
+    let output = fs::canonicalize(&output)?;
+    println!("{:<6} {:<6} {:<44} {}", "node", "port", "validator", "enode");
+    for (node, key) in nodes.iter().zip(&keys) {
+        let dir = output.join(format!("node{}", node.index));
+        fs::create_dir_all(dir.join("geth"))?;
+        fs::write(dir.join("geth").join("nodekey"), hex::encode(node.node_key.secret_bytes()))?;
+        let info = write_validator_keys(&dir, key, &seed, &opt.password, &scrypt)?;
+        let config = node_toml_config(&nodes, node, opt.ip, &dir);
+        fs::write(dir.join("config.toml"), toml::to_string(&config)?)?;
+
+        let validator = if signers.contains(&info.address) {
+            format!("{:?}", info.address)
+        } else {
+            String::from("-")
+        };
+        println!("{:<6} {:<6} {:<44} {}", node.index, node.port, validator, node.enode(opt.ip));
+    }
+
+    chain_spec.p2p.bootnodes = nodes.iter().map(|node| node.enode(opt.ip)).collect();
+    let path = output.join(format!("{}.ron", chain_spec.name));
+    fs::write(&path, ron::ser::to_string_pretty(&chain_spec, ron::ser::PrettyConfig::new())?)?;
+    println!("akula's chain spec saved in path: {:?}", &path.to_str());
+    Ok(())
+}
+
+#[derive(Parser)]
+struct EnodeOpt {
+    #[clap(long, help = "input the nodekey file location.")]
+    pub nodekey: Option<String>,
+    #[clap(long, help = "input the node's config.toml file location, for its ListenAddr.")]
+    pub config: Option<String>,
+    #[clap(long, help = "input the cluster dir, scanning its node dirs for nodekeys and config.toml files.")]
+    pub cluster: Option<String>,
+    #[clap(long, help = "input the chain spec .ron file location, to write with the cluster's nodes as bootnodes.")]
+    pub spec: Option<String>,
+    #[clap(long, default_value = "127.0.0.1", help = "IP of the nodes whose ListenAddr has none.")]
+    pub ip: IpAddr,
+    #[clap(long, help = "output path.")]
+    pub output: Option<String>,
+}
+
+fn enode(opt: EnodeOpt) -> anyhow::Result<()> {
+    let dir = match (&opt.nodekey, &opt.cluster) {
+        (Some(nodekey), None) => {
+            let config = match &opt.config {
+                Some(path) => toml::from_str(&fs::read_to_string(path)?)?,
+                None => bail!("pass --config with --nodekey"),
+            };
+            println!("{}", node_enode(nodekey, &config, opt.ip)?);
+            return Ok(());
+        }
+        (None, Some(dir)) => dir,
+        _ => bail!("pass one of --nodekey and --cluster"),
+    };
+
+    let enodes = cluster_enodes(dir, opt.ip)?;
+    if enodes.is_empty() {
+        bail!("no node dirs with a nodekey in {}", dir);
+    }
+    for enode in &enodes {
+        println!("{}", enode);
+    }
+    if let Some(path) = &opt.spec {
+        let mut chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(path)?)?;
+        chain_spec.p2p.bootnodes = enodes;
+        let output = opt.output.unwrap_or(String::from("."));
+        let path = Path::new(&output).join(format!("{}.ron", chain_spec.name));
+        fs::write(&path, ron::ser::to_string_pretty(&chain_spec, ron::ser::PrettyConfig::new())?)?;
+        println!("akula's chain spec saved in path: {:?}", &path.to_str());
+    }
+    Ok(())
+}
+
+#[derive(Parser)]
+struct DnstreeOpt {
+    #[clap(long, help = "domain the tree is published under.")]
+    pub domain: String,
+    #[clap(long, help = "input the file location of the hex secp256k1 key to sign the tree with.")]
+    pub key: String,
+    #[clap(long, help = "input a file location of ENRs or enode URLs, one per line.")]
+    pub nodes: Option<String>,
+    #[clap(long, help = "input the cluster dir, whose nodes are signed into records with their nodekeys.")]
+    pub cluster: Option<String>,
+    #[clap(long, default_value = "127.0.0.1", help = "IP of the cluster nodes whose ListenAddr has none.")]
+    pub ip: IpAddr,
+    #[clap(long, use_value_delimiter = true, help = "comma separated enrtree:// URLs of other trees to link.")]
+    pub links: Vec<String>,
+    #[clap(long, default_value = "1", help = "sequence number of the tree, to raise on every update.")]
+    pub seq: u64,
+    #[clap(long, help = "input the chain spec .ron file location, to write with the tree URL as its dns.")]
+    pub spec: Option<String>,
+    #[clap(long, help = "output path.")]
+    pub output: Option<String>,
+}
+
+fn dnstree(opt: DnstreeOpt) -> anyhow::Result<()> {
+    let key = read_node_key(&opt.key)?;
+    let node_dirs = match &opt.cluster {
+        Some(dir) => read_node_dirs(dir)?,
+        None => vec![],
+    };
+
+    let mut records = Vec::new();
+    if let Some(path) = &opt.nodes {
+        let source = fs::read_to_string(path)?;
+        let nodes: Vec<String> = source.lines().map(str::trim).filter(|l| !l.is_empty()).map(String::from).collect();
+        let nodes = parse_node_list(&nodes, Some(&source)).map_err(|invalid| {
+            let lines: Vec<String> = invalid.iter().map(|node| format!("  {}", node)).collect();
+            anyhow::anyhow!("invalid nodes in {}:\n{}", path, lines.join("\n"))
+        })?;
+        for node in nodes {
+            match node {
+                NodeRecord::Enr(enr) => records.push(enr),
+                // a record must be signed by the node itself
+                NodeRecord::Enode(enode) => match node_dirs.iter().find(|dir| {
+                    PublicKey::from_secret_key(SECP256K1, &dir.node_key) == enode.public_key()
+                }) {
+                    Some(dir) => records.push(Enr::build(
+                        &dir.node_key,
+                        opt.seq,
+                        Some(enode.ip),
+                        Some(enode.tcp_port),
+                        Some(enode.udp_port),
+                    )),
+                    None => bail!("no nodekey of {} in --cluster to sign its record with", enode),
+                },
+            }
+        }
+    } else {
+        records = node_dirs.iter().map(|dir| dir.enr(opt.ip, opt.seq)).collect();
+    }
+    if records.is_empty() {
+        bail!("no nodes, pass --nodes or --cluster");
+    }
+    for link in &opt.links {
+        parse_tree_url(link)?;
+    }
+
+    let mut tree = DnsTree::build(&records, &opt.links, opt.seq);
+    tree.sign(&key)?;
+    let url = tree_url(&PublicKey::from_secret_key(SECP256K1, &key), &opt.domain);
+
+    let output = opt.output.unwrap_or(String::from("."));
+    let path = Path::new(&output).join(format!("{}.zone", opt.domain.trim_end_matches('.')));
+    fs::write(&path, tree.to_zone(&opt.domain))?;
+    println!("{}", url);
+    println!("tree of {} records and {} links saved in path: {:?}", records.len(), opt.links.len(), &path.to_str());
+    if let Some(path) = &opt.spec {
+        let mut chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(path)?)?;
+        chain_spec.p2p.dns = Some(url);
+        let path = Path::new(&output).join(format!("{}.ron", chain_spec.name));
+        fs::write(&path, ron::ser::to_string_pretty(&chain_spec, ron::ser::PrettyConfig::new())?)?;
+        println!("akula's chain spec saved in path: {:?}", &path.to_str());
+    }
+    Ok(())
+}
+
+#[derive(Parser)]
+struct DnscheckOpt {
+    #[clap(long, help = "input the zone file location.")]
+    pub zone: String,
+    #[clap(long, help = "enrtree:// URL of the tree.")]
+    pub url: Option<String>,
+    #[clap(long, help = "input the chain spec .ron file location, to take the tree URL from its dns instead.")]
+    pub spec: Option<String>,
+}
+
+fn dnscheck(opt: DnscheckOpt) -> anyhow::Result<()> {
+    let url = match (opt.url, &opt.spec) {
+        (Some(url), None) => url,
+        (None, Some(path)) => {
+            let chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(path)?)?;
+            match chain_spec.p2p.dns {
+                Some(url) => url,
+                None => bail!("no dns in the p2p params of {}", chain_spec.name),
+            }
+        }
+        _ => bail!("pass one of --url and --spec"),
+    };
+    let tree = verify_zone(&url, &fs::read_to_string(&opt.zone)?)?;
+    for enr in &tree.records {
+        match enr.to_enode() {
+            Some(enode) => println!("{}", enode),
+            None => println!("{}", enr),
+        }
+    }
+    for link in &tree.links {
+        println!("{}", link);
+    }
+    println!("tree seq {} of {} records and {} links is valid for {}", tree.seq, tree.records.len(), tree.links.len(), url);
+    Ok(())
+}
+
+#[derive(Parser)]
+struct TopologyOpt {
+    #[clap(long, help = "input the cluster dir, holding a dir with a nodekey and a config.toml per node.")]
+    pub cluster: String,
+    #[clap(long, help = "input the chain spec .ron file location, to check its genesis validators can reach each other.")]
+    pub spec: Option<String>,
+    #[clap(long, default_value = "127.0.0.1", help = "IP of the nodes whose ListenAddr has none.")]
+    pub ip: IpAddr,
+    #[clap(long, help = "output file location of the graph in DOT.")]
+    pub dot: Option<String>,
+}
+
+fn topology(opt: TopologyOpt) -> anyhow::Result<()> {
+    let validators = match &opt.spec {
+        Some(path) => match ron::from_str::<ChainSpec>(&fs::read_to_string(path)?)?.genesis.seal {
+            Seal::Parlia { signers, .. } => signers,
+            _ => vec![],
+        },
+        None => vec![],
+    };
+    let dirs = read_node_dirs(&opt.cluster)?;
+    if dirs.is_empty() {
+        bail!("no node dirs with a nodekey in {}", opt.cluster);
+    }
+    let accounts = dirs
+        .iter()
+        .map(|dir| {
+            let keystore = dir.path.join("keystore");
+            if keystore.is_dir() {
+                read_keystore_addresses(keystore)
+            } else {
+                Ok(vec![])
+            }
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let topology = Topology::new(&dirs, accounts, opt.ip);
+
+    println!("{:<12} {:<6} {:<6} {:<6} {}", "node", "port", "static", "peers", "validator");
+    for (i, node) in topology.nodes.iter().enumerate() {
+        let dialed = topology.peerings.iter().filter(|p| p.from == i && p.kind == PeerKind::Static).count();
+        let peers = topology
+            .peerings
+            .iter()
+            .filter(|p| p.kind == PeerKind::Static)
+            .filter_map(|p| match p.target {
+                PeerTarget::Node { index, reachable: true } if p.from == i => Some(index),
+                PeerTarget::Node { index, reachable: true } if index == i => Some(p.from),
+                _ => None,
+            })
+            .collect::<BTreeSet<_>>()
+            .len();
+        let validator = match node.accounts.iter().find(|a| validators.contains(a)) {
+            Some(validator) => format!("{:?}", validator),
+            None => String::from("-"),
+        };
+        println!("{:<12} {:<6} {:<6} {:<6} {}", node.name, node.port, dialed, peers, validator);
+    }
+    if let Some(path) = &opt.dot {
+        fs::write(path, topology.to_dot(&validators))?;
+        println!("graph saved in path: {:?}", path);
+    }
+
+    let issues = topology.issues(&validators);
+    for issue in &issues {
+        println!("{}", issue);
+    }
+    if !issues.is_empty() {
+        bail!("{} issues in the topology of {}", issues.len(), opt.cluster);
+    }
+    println!("{} nodes reach each other", topology.nodes.len());
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let opt: Opt = Opt::parse();
+
+    match opt.cmd {
+        Command::Keygen(opt) => keygen(opt),
+        Command::Genesis(opt) => genesis(opt),
+        Command::Cluster(opt) => cluster(opt),
+        Command::Enode(opt) => enode(opt),
+        Command::Dnstree(opt) => dnstree(opt),
+        Command::Dnscheck(opt) => dnscheck(opt),
+        Command::Topology(opt) => topology(opt),
+    }
+}
@@ -0,0 +1,74 @@
+use std::fs;
+use std::path::Path;
+use anyhow::bail;
+use akula_tools::consensus::parlia::*;
+use akula_tools::models::chainspec::*;
+use akula_tools::models::checkpoint::*;
+use akula_tools::models::header::*;
+
+use clap::Parser;
+use ethereum_types::H256;
+
+#[derive(Parser)]
+#[clap(name = "Akula-tools", about = "export a trusted epoch header as an Akula sync checkpoint.")]
+struct Opt {
+    #[clap(long, help = "input the base chain spec .ron file location.")]
+    pub spec: String,
+    #[clap(long, help = "input the trusted epoch header file location.")]
+    pub header: String,
+    #[clap(long, arg_enum, help = "header file format, detected from the content by default.")]
+    pub format: Option<HeaderFormat>,
+    #[clap(long, help = "trusted hash the header must match.")]
+    pub hash: Option<H256>,
+    #[clap(long, help = "output path.")]
+    pub output: Option<String>,
+}
+
+fn main() -> anyhow::Result<()> {
+    let opt: Opt = Opt::parse();
+
+    let chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(&opt.spec)?)?;
+    let epoch = match chain_spec.consensus.parlia_epoch() {
+        Some(0) => return Err(ParliaError::ZeroEpoch.into()),
+        Some(epoch) => epoch,
+        None => bail!("chain spec {} is not a parlia chain", chain_spec.name),
+    };
+
+    let mut headers = parse_headers(&fs::read(&opt.header)?, opt.format)?;
+    if headers.len() != 1 {
+        bail!("expected a single header, got {}", headers.len());
+    }
+    let (header, reported_hash) = headers.remove(0);
+    if header.number.0 % epoch != 0 {
+        return Err(ParliaError::NotEpochHeader { number: header.number, epoch }.into());
+    }
+
+    // the checkpoint hash must be the one of the header we decoded validators from
+    let hash = header.hash();
+    for expected in reported_hash.into_iter().chain(opt.hash) {
+        if expected != hash {
+            return Err(ParliaError::WrongHeaderHash { number: header.number, expected, got: hash }.into());
+        }
+    }
+
+    let (validators, bls_pub_keys) = parse_epoch_validators(
+        &header.extra_data,
+        chain_spec.is_boneh(&header.number),
+    )?;
+    if let Some(bls_pub_keys) = &bls_pub_keys {
+        validate_bls_pub_keys(&validators, bls_pub_keys)?;
+    }
+    let checkpoint = Checkpoint {
+        number: header.number,
+        hash,
+        timestamp: header.timestamp,
+        validators,
+        bls_pub_keys,
+    };
+
+    let output = opt.output.unwrap_or(String::from("."));
+    let path = Path::new(&output).join(format!("{}.checkpoint.ron", chain_spec.name));
+    fs::write(&path, ron::ser::to_string_pretty(&checkpoint, ron::ser::PrettyConfig::new())?)?;
+    println!("akula's checkpoint at block {} ({:?}) saved in path: {:?}", checkpoint.number, checkpoint.hash, &path.to_str());
+    Ok(())
+}
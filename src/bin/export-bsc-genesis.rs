@@ -1,15 +1,11 @@
-use std::collections::{BTreeMap, BTreeSet};
 use std::fs;
 use std::path::Path;
-use anyhow::bail;
+use akula_tools::convert::*;
 use akula_tools::models::*;
-use akula_tools::models::chainspec::*;
+use akula_tools::systemcontracts::*;
 
+use anyhow::bail;
 use clap::Parser;
-use ethereum_types::{Address, U256};
-use hex_literal::hex;
-use akula_tools::models::bls::*;
-use std::str::FromStr;
 
 #[derive(Parser)]
 #[clap(name = "Akula-tools", about = "a set of tools for akula.")]
@@ -22,138 +18,79 @@ struct Opt {
     pub genesis: String,
     #[clap(long, help = "input the config.toml file location.")]
     pub config: String,
+    #[clap(long, arg_enum, use_value_delimiter = true, help = "comma separated node lists of config.toml to merge into the bootnodes, static by default.")]
+    pub bootnodes: Vec<NodeSource>,
+    #[clap(long, help = "fail on keys of genesis.json the converter does not know, instead of warning.")]
+    pub deny_unknown: bool,
+    #[clap(long, help = "BSC geth's core/systemcontracts directory to read the system contract upgrades from, instead of the embedded tables.")]
+    pub system_contracts: Option<String>,
+    #[clap(long, help = "network of the system contract tables, by the chain id by default.")]
+    pub system_contracts_network: Option<String>,
 }
 
 fn main() -> anyhow::Result<()> {
     let opt: Opt = Opt::parse();
 
-    let genesis: bsc::Genesis = serde_json::from_str(&fs::read_to_string(opt.genesis)?)?;
-    let config: bsc::TomlConfig = toml::from_str(&fs::read_to_string(opt.config)?)?;
-
-    let mut chain_spec = ChainSpec {
-        name: opt.name.unwrap_or(String::from("BSC-devnet")),
-        consensus: ConsensusParams {
-            seal_verification: SealVerificationParams::Parlia {
-                period: genesis.config.parlia.period,
-                epoch: genesis.config.parlia.epoch
-            },
-            eip1559_block: None
-        },
-        upgrades: Upgrades {
-            homestead: genesis.config.homestead_block,
-            tangerine: genesis.config.eip_150_block,
-            spurious: genesis.config.eip_155_block,
-            byzantium: genesis.config.byzantium_block,
-            constantinople: genesis.config.constantinople_block,
-            petersburg: genesis.config.petersburg_block,
-            istanbul: genesis.config.istanbul_block,
-            berlin: genesis.config.berlin_block,
-            london: genesis.config.london_block,
-            // not support in bsc
-            paris: None,
-            // bsc fork start
-            ramanujan: genesis.config.ramanujan_block,
-            niels: genesis.config.niels_block,
-            mirrorsync: genesis.config.mirror_sync_block,
-            bruno: genesis.config.bruno_block,
-            euler: genesis.config.euler_block,
-            gibbs: genesis.config.gibbs_block,
-            boneh: genesis.config.boneh_block,
-            lynn: genesis.config.lynn_block
-        },
-        params: Params {
-            chain_id: ChainId(genesis.config.chain_id),
-            network_id: NetworkId(genesis.config.chain_id),
-            additional_forks: BTreeSet::new(),
-        },
-        genesis: Genesis {
-            number: BlockNumber(genesis.number.as_u64()),
-            author: genesis.coinbase,
-            gas_limit: genesis.gas_limit.as_u64(),
-            timestamp: genesis.timestamp.as_u64(),
-            seal: Seal::Unknown,
-            base_fee_per_gas: None
-        },
-        contracts: Default::default(),
-        balances: Default::default(),
-        p2p: P2PParams {
-            bootnodes: config.node.p2p.static_nodes,
-            dns: None
-        }
-    };
-
-    // parse contracts and balances
-    let mut contracts = BTreeMap::new();
-    let mut balances = BTreeMap::new();
-    for (addr, account) in genesis.alloc {
-        balances.insert(addr, if account.balance.starts_with("0x") {
-            U256::from_str(&account.balance)?
-        } else {
-            U256::from_dec_str(&account.balance)?
-        });
-        if let Some(code) = account.code {
-            contracts.insert(addr, Contract::Contract {
-                code: hex::decode(code.strip_prefix("0x").unwrap_or(&code))
-                    .map_err(|e| e)?
-                    .into()
-            });
+    let genesis_source = fs::read_to_string(opt.genesis)?;
+    let mut genesis: bsc::Genesis = serde_json::from_str(&genesis_source)?;
+    let unknown = unknown_genesis_keys(&genesis_source)?;
+    if opt.deny_unknown && !unknown.is_empty() {
+        bail!("unknown keys in genesis.json: {}", unknown.join(", "));
+    }
+    for key in &unknown {
+        eprintln!("warning: unknown key {} in genesis.json is dropped", key);
+    }
+    let source = fs::read_to_string(opt.config)?;
+    let config: bsc::TomlConfig = toml::from_str(&source)?;
+    let overrides = apply_fork_overrides(&mut genesis.config, &config.eth);
+    for fork_override in &overrides {
+        match fork_override.effect {
+            OverrideEffect::Applied { .. } => eprintln!("override: {}", fork_override),
+            OverrideEffect::Ignored => eprintln!("warning: {}", fork_override),
         }
     }
-    chain_spec.contracts.insert(chain_spec.genesis.number, contracts);
-    chain_spec.balances.insert(chain_spec.genesis.number, balances);
 
-    // set base_fee_per_gas
-    if chain_spec.is_london(&chain_spec.genesis.number) {
-        chain_spec.genesis.base_fee_per_gas = Some(U256::from(1000000000_u128));
+    let bootnode_sources = if opt.bootnodes.is_empty() { DEFAULT_BOOTNODE_SOURCES } else { &opt.bootnodes };
+    let mut chain_spec = genesis_to_chain_spec(
+        &genesis,
+        opt.name.unwrap_or(String::from("BSC-devnet")),
+        p2p_params(&config, &source, bootnode_sources)?,
+    )?;
+    for warning in apply_toml_config(&mut chain_spec, &config) {
+        eprintln!("warning: {}", warning);
     }
-
-    // parse signers and bls keys
-    let extra_data = genesis.extra_data;
-    let extra_len = extra_data.len();
-    let mut signers = Vec::new();
-    let mut bls_keys = None;
-    if !chain_spec.is_boneh(&chain_spec.genesis.number) {
-        let val_bytes = &extra_data[EXTRA_VANITY_LEN..extra_len - EXTRA_SEAL_LEN];
-        let count = val_bytes.len() / EXTRA_VALIDATOR_LEN;
-        for i in 0..count {
-            let start = i * EXTRA_VALIDATOR_LEN;
-            signers.push(Address::from_slice(&val_bytes[start..start + EXTRA_VALIDATOR_LEN]));
+    let mut report = conversion_report(&genesis, &config, bootnode_sources, &overrides, &unknown, &chain_spec);
+    let network = opt.system_contracts_network.as_deref().unwrap_or_else(|| network(chain_spec.params.chain_id.0));
+    let registry = match &opt.system_contracts {
+        Some(dir) => SystemContractRegistry::load(Path::new(dir), network)?,
+        None => {
+            let registry = SystemContractRegistry::embedded(network)?;
+            let unfilled = unfilled_upgrades(&chain_spec, &registry);
+            if !unfilled.is_empty() {
+                bail!(
+                    "the embedded {} table has no system contract upgrades for {} after genesis, pass --system-contracts to read them from geth",
+                    network,
+                    unfilled.join(", ")
+                );
+            }
+            registry
         }
-    } else {
-        let mut tmp = Vec::new();
-
-        let count = extra_data[EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH - 1] as usize;
-        let start = EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH;
-        let end = start + count * EXTRA_VALIDATOR_LEN_IN_BONEH;
-        let val_bytes = &extra_data[start..end];
-
-        for i in 0..count {
-            let start = i * EXTRA_VALIDATOR_LEN_IN_BONEH;
-            let end = start + EXTRA_VALIDATOR_LEN;
-            signers.push(Address::from_slice(&val_bytes[start..end]));
-
-            let start = i * EXTRA_VALIDATOR_LEN_IN_BONEH + EXTRA_VALIDATOR_LEN;
-            let end = i * EXTRA_VALIDATOR_LEN_IN_BONEH + EXTRA_VALIDATOR_LEN_IN_BONEH;
-            tmp.push(BLSPublicKey::from_slice(&val_bytes[start..end]));
+    };
+    let upgrades = apply_system_contract_upgrades(&mut chain_spec, &registry);
+    for upgrade in &upgrades {
+        if let UpgradeEffect::Scheduled(block) = upgrade.effect {
+            eprintln!("upgrade: {} at block {}: {}", upgrade.fork, block, upgrade.contracts.join(", "));
         }
-        bls_keys = Some(tmp);
     }
-    chain_spec.genesis.seal = Seal::Parlia {
-        vanity: hex!("0000000000000000000000000000000000000000000000000000000000000000").into(),
-        score: if genesis.difficulty.as_u64() == 1 {
-            BlockScore::NoTurn
-        } else if genesis.difficulty.as_u64() == 2 {
-            BlockScore::InTurn
-        } else {
-            return bail!("wrong parlia difficulty");
-        },
-        signers,
-        bls_pub_keys: bls_keys
-    };
+    report.system_contract_upgrades(network, &upgrades);
 
     let output = opt.output.unwrap_or(String::from("."));
     let path = Path::new(&output).join(format!("{}.ron", chain_spec.name));
     fs::write(&path, ron::ser::to_string_pretty(&chain_spec, ron::ser::PrettyConfig::new())?)?;
     println!("akula's chain spec saved in path: {:?}", &path.to_str());
+    let report_path = Path::new(&output).join(format!("{}.report.txt", chain_spec.name));
+    fs::write(&report_path, report.to_string())?;
+    fs::write(Path::new(&output).join(format!("{}.report.json", chain_spec.name)), serde_json::to_string_pretty(&report)?)?;
+    println!("conversion report saved in path: {:?}", &report_path.to_str());
     Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,310 @@
+use std::fs;
+use anyhow::bail;
+use akula_tools::consensus::parlia::*;
+use akula_tools::crypto::bls_public;
+use akula_tools::keystore::*;
+use akula_tools::models::bsc::{TomlConfig, TomlNode};
+use akula_tools::models::chainspec::*;
+use akula_tools::models::checkpoint::*;
+use akula_tools::models::header::*;
+
+use clap::{Parser, Subcommand};
+use ethereum_types::{Address, H256};
+
+#[derive(Parser)]
+#[clap(name = "Akula-tools", about = "offline tools for the parlia consensus of bsc.")]
+struct Opt {
+    #[clap(subcommand)]
+    pub cmd: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[clap(about = "verify a chain of headers, the first one is trusted and must be an epoch header.")]
+    Verify(VerifyOpt),
+    #[clap(about = "show who is in turn for a block, and when every validator may seal it.")]
+    Inturn(InturnOpt),
+    #[clap(about = "simulate block production of the genesis validators, some of them possibly offline.")]
+    Simulate(SimulateOpt),
+    #[clap(about = "follow the justified and finalized blocks along a chain of headers, the first one is trusted.")]
+    Finality(FinalityOpt),
+    #[clap(about = "check the BLS vote key of a validator node against the genesis validators.")]
+    Votekey(VotekeyOpt),
+}
+
+#[derive(Parser)]
+struct VerifyOpt {
+    #[clap(long, help = "input the chain spec .ron file location.")]
+    pub spec: String,
+    #[clap(long, help = "input the consecutive headers file location.")]
+    pub headers: String,
+    #[clap(long, arg_enum, help = "headers file format, detected from the content by default.")]
+    pub format: Option<HeaderFormat>,
+}
+
+fn verify(opt: VerifyOpt) -> anyhow::Result<()> {
+    let chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(&opt.spec)?)?;
+    let mut headers = parse_headers(&fs::read(&opt.headers)?, opt.format)?.into_iter();
+    let (anchor, _) = match headers.next() {
+        Some(anchor) => anchor,
+        None => bail!("no header in {}", opt.headers),
+    };
+
+    let mut verifier = HeaderVerifier::new(&chain_spec, anchor)?;
+    let mut count = 0;
+    for (header, reported_hash) in headers {
+        let hash = header.hash();
+        let res = match reported_hash {
+            Some(expected) if expected != hash => {
+                Err(ParliaError::WrongHeaderHash { number: header.number, expected, got: hash })
+            }
+            _ => verifier.verify(&header),
+        };
+        if let Err(e) = res {
+            bail!("invalid header {} ({:?}): {}", header.number, hash, e);
+        }
+        count += 1;
+    }
+    println!("{} headers verified, head at {} ({:?})", count, verifier.snapshot().number, verifier.snapshot().hash);
+    Ok(())
+}
+
+#[derive(Parser)]
+struct InturnOpt {
+    #[clap(long, help = "input the chain spec .ron file location.")]
+    pub spec: String,
+    #[clap(long, help = "number of the block to seal.")]
+    pub number: BlockNumber,
+    #[clap(long, help = "timestamp of the parent block.")]
+    pub parent_timestamp: u64,
+    #[clap(long, use_value_delimiter = true, help = "comma separated validators, the genesis ones by default.")]
+    pub validators: Vec<Address>,
+    #[clap(long, help = "take the validators from a checkpoint .ron file instead.")]
+    pub checkpoint: Option<String>,
+}
+
+fn inturn(opt: InturnOpt) -> anyhow::Result<()> {
+    let chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(&opt.spec)?)?;
+    let period = chain_spec.consensus.parlia_period().ok_or(ParliaError::NotParliaChain)?;
+
+    let mut validators = if let Some(path) = &opt.checkpoint {
+        let checkpoint: Checkpoint = ron::from_str(&fs::read_to_string(path)?)?;
+        checkpoint.validators
+    } else if !opt.validators.is_empty() {
+        opt.validators
+    } else if let Seal::Parlia { signers, .. } = &chain_spec.genesis.seal {
+        signers.clone()
+    } else {
+        bail!("no validators in the genesis of {}", chain_spec.name)
+    };
+    validators.sort();
+    validators.dedup();
+    if validators.is_empty() {
+        bail!("no validators to seal block {}", opt.number);
+    }
+
+    let is_ramanujan = chain_spec.is_ramanujan(&opt.number);
+    println!("block {}, parent timestamp {}, period {}s, ramanujan {}", opt.number, opt.parent_timestamp, period, is_ramanujan);
+    println!("{:<44} {:<10} {:>8} {:>20}", "validator", "difficulty", "back off", "earliest timestamp");
+    for turn in seal_turns(&validators, opt.number, opt.parent_timestamp, period, is_ramanujan) {
+        println!(
+            "{:<44} {:<10} {:>8} {:>20}",
+            format!("{:?}", turn.validator),
+            format!("{:?}", turn.score),
+            turn.back_off_time,
+            turn.earliest_timestamp
+        );
+    }
+    Ok(())
+}
+
+#[derive(Parser)]
+struct SimulateOpt {
+    #[clap(long, help = "input the chain spec .ron file location.")]
+    pub spec: String,
+    #[clap(long, help = "number of blocks to seal after genesis.")]
+    pub blocks: u64,
+    #[clap(long, help = "input the scenario .ron file location, listing offline windows of validators.")]
+    pub scenario: Option<String>,
+    #[clap(long, help = "output the simulated blocks and statistics as JSON to this file.")]
+    pub output: Option<String>,
+    #[clap(long, help = "print every simulated block.")]
+    pub trace: bool,
+}
+
+fn simulate(opt: SimulateOpt) -> anyhow::Result<()> {
+    let chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(&opt.spec)?)?;
+    let scenario: Scenario = match &opt.scenario {
+        Some(path) => ron::from_str(&fs::read_to_string(path)?)?,
+        None => Scenario::default(),
+    };
+
+    let simulation = akula_tools::consensus::parlia::simulate(&chain_spec, &scenario, opt.blocks)?;
+    if opt.trace {
+        for block in &simulation.blocks {
+            println!(
+                "{:>10} {:>12} {:?} {:?}",
+                block.number,
+                block.timestamp,
+                block.sealer,
+                block.score
+            );
+        }
+    }
+    println!("{:<44} {:>8} {:>8} {:>8} {:>8}", "validator", "sealed", "in turn", "missed", "lockouts");
+    for (validator, stats) in &simulation.validators {
+        println!(
+            "{:<44} {:>8} {:>8} {:>8} {:>8}",
+            format!("{:?}", validator),
+            stats.sealed,
+            stats.inturn_slots,
+            stats.missed_inturn,
+            stats.lockouts
+        );
+    }
+    if let Some(last) = simulation.blocks.last() {
+        let first_timestamp = chain_spec.genesis.timestamp;
+        println!(
+            "{} blocks sealed in {}s, head at {}",
+            simulation.blocks.len(),
+            last.timestamp - first_timestamp,
+            last.number
+        );
+    }
+    if let Some(path) = &opt.output {
+        fs::write(path, serde_json::to_string_pretty(&simulation)?)?;
+    }
+    if let Some(number) = simulation.halted_at {
+        bail!("chain halted at block {}, no online validator may seal it", number);
+    }
+    Ok(())
+}
+
+#[derive(Parser)]
+struct FinalityOpt {
+    #[clap(long, help = "input the chain spec .ron file location.")]
+    pub spec: String,
+    #[clap(long, help = "input the consecutive headers file location.")]
+    pub headers: String,
+    #[clap(long, arg_enum, help = "headers file format, detected from the content by default.")]
+    pub format: Option<HeaderFormat>,
+    #[clap(long, help = "fail unless the last header finalizes at least this block.")]
+    pub min_finalized: Option<BlockNumber>,
+}
+
+fn finality(opt: FinalityOpt) -> anyhow::Result<()> {
+    let chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(&opt.spec)?)?;
+    let mut headers = parse_headers(&fs::read(&opt.headers)?, opt.format)?.into_iter();
+    let (anchor, _) = match headers.next() {
+        Some(anchor) => anchor,
+        None => bail!("no header in {}", opt.headers),
+    };
+
+    let mut tracker = FinalityTracker::new(&chain_spec, anchor)?;
+    let mut last = tracker.finality();
+    for (header, _) in headers {
+        if chain_spec.upgrades.boneh == Some(header.number) {
+            println!("block {}: boneh activated", header.number);
+        }
+        let finality = match tracker.push(&header) {
+            Ok(finality) => finality,
+            Err(e) => bail!("invalid header {} ({:?}): {}", header.number, header.hash(), e),
+        };
+        if finality.justified != last.justified || finality.finalized != last.finalized {
+            println!(
+                "block {}: justified {}, finalized {}",
+                finality.number,
+                format_block(finality.justified),
+                format_block(finality.finalized)
+            );
+        }
+        last = finality;
+    }
+
+    println!(
+        "head at {}, justified {}, finalized {}",
+        last.number,
+        format_block(last.justified),
+        format_block(last.finalized)
+    );
+    if let Some(min_finalized) = opt.min_finalized {
+        match last.finalized {
+            Some((finalized, _)) if finalized >= min_finalized => {}
+            _ => bail!("finalized block is not at {} yet", min_finalized),
+        }
+    }
+    Ok(())
+}
+
+fn format_block(block: Option<(BlockNumber, H256)>) -> String {
+    match block {
+        Some((number, hash)) => format!("{} ({:?})", number, hash),
+        None => String::from("unknown"),
+    }
+}
+
+#[derive(Parser)]
+struct VotekeyOpt {
+    #[clap(long, help = "input the chain spec .ron file location.")]
+    pub spec: String,
+    #[clap(long, help = "input the node's config.toml file location, for its BLSWalletDir and BLSPasswordFile.")]
+    pub config: Option<String>,
+    #[clap(long, help = "BLS wallet dir, or dir of EIP-2335 keystores, instead of the config's BLSWalletDir.")]
+    pub wallet_dir: Option<String>,
+    #[clap(long, help = "wallet password file instead of the config's BLSPasswordFile.")]
+    pub password_file: Option<String>,
+}
+
+fn votekey(opt: VotekeyOpt) -> anyhow::Result<()> {
+    let chain_spec: ChainSpec = ron::from_str(&fs::read_to_string(&opt.spec)?)?;
+    let node = match &opt.config {
+        Some(path) => Some(toml::from_str::<TomlConfig>(&fs::read_to_string(path)?)?.node),
+        None => None,
+    };
+    let wallet_dir = match (opt.wallet_dir, &node) {
+        (Some(dir), _) => dir,
+        (None, Some(TomlNode { bls_wallet_dir: Some(dir), .. })) => dir.clone(),
+        (None, Some(_)) => bail!("no BLSWalletDir in {}, pass --wallet-dir", opt.config.as_deref().unwrap_or_default()),
+        (None, None) => bail!("pass --config or --wallet-dir"),
+    };
+    let password_file = match (opt.password_file, &node) {
+        (Some(path), _) => path,
+        (None, Some(TomlNode { bls_password_file: Some(path), .. })) => path.clone(),
+        (None, Some(_)) => bail!("no BLSPasswordFile in {}, pass --password-file", opt.config.as_deref().unwrap_or_default()),
+        (None, None) => bail!("pass --config or --password-file"),
+    };
+
+    let (signers, bls_pub_keys) = match &chain_spec.genesis.seal {
+        Seal::Parlia { signers, bls_pub_keys: Some(bls_pub_keys), .. } => (signers, bls_pub_keys),
+        _ => bail!("no BLS keys in the genesis of {}", chain_spec.name),
+    };
+    let password = read_password_file(&password_file)?;
+    let secrets = read_bls_wallet(&wallet_dir, &password)?;
+    for (i, secret) in secrets.iter().enumerate() {
+        let vote_key = bls_public(secret);
+        let validator = bls_pub_keys.iter().position(|key| *key == vote_key).map(|i| signers[i]);
+        match validator {
+            Some(validator) => println!("{:?} is the vote key of validator {:?}", vote_key, validator),
+            None => {
+                // geth votes with the first key of the wallet
+                if i == 0 {
+                    bail!("vote key {:?} in {} is not one of the genesis validators", vote_key, wallet_dir);
+                }
+                println!("{:?} is not the vote key of a genesis validator", vote_key);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn main() -> anyhow::Result<()> {
+    let opt: Opt = Opt::parse();
+
+    match opt.cmd {
+        Command::Verify(opt) => verify(opt),
+        Command::Inturn(opt) => inturn(opt),
+        Command::Simulate(opt) => simulate(opt),
+        Command::Finality(opt) => finality(opt),
+        Command::Votekey(opt) => votekey(opt),
+    }
+}
@@ -0,0 +1,183 @@
+use super::*;
+use crate::models::{chainspec::*, header::BlockHeader};
+use serde::Serialize;
+
+/// Fast finality state of the chain at a header.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct Finality {
+    pub number: BlockNumber,
+    pub hash: H256,
+    pub justified: Option<(BlockNumber, H256)>,
+    pub finalized: Option<(BlockNumber, H256)>,
+}
+
+impl From<&Snapshot> for Finality {
+    fn from(snap: &Snapshot) -> Self {
+        Self {
+            number: snap.number,
+            hash: snap.hash,
+            justified: snap.justified(),
+            finalized: snap.finalized(),
+        }
+    }
+}
+
+/// FinalityTracker verifies consecutive headers and follows the justified and finalized
+/// blocks as their vote attestations move them under BEP-126: a block is justified once
+/// 2/3 of the validators attest it, and its justified source is finalized if it is the
+/// block right before it.
+pub struct FinalityTracker<'a> {
+    verifier: HeaderVerifier<'a>,
+}
+
+impl<'a> FinalityTracker<'a> {
+    /// Starts tracking after a trusted epoch header, the justified and finalized blocks
+    /// are unknown until the first attestation.
+    pub fn new(chain_spec: &'a ChainSpec, anchor: BlockHeader) -> Result<Self, ParliaError> {
+        Ok(Self {
+            verifier: HeaderVerifier::new(chain_spec, anchor)?,
+        })
+    }
+
+    pub fn finality(&self) -> Finality {
+        Finality::from(self.verifier.snapshot())
+    }
+
+    /// push verifies the next header, with its attestation, and returns the finality after it.
+    pub fn push(&mut self, header: &BlockHeader) -> Result<Finality, ParliaError> {
+        self.verifier.verify(header)?;
+        Ok(self.finality())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::*, *};
+    use crate::models::vote::VoteData;
+
+    #[test]
+    fn track_justified_and_finalized() {
+        let mut chain_spec = chain_spec(3, 10);
+        chain_spec.upgrades.boneh = Some(BlockNumber(0));
+        let keys = validator_keys(3);
+        let bls_secrets = bls_keys(3);
+        let mut genesis = genesis_header(None);
+        genesis.extra_data = boneh_extra_data(&keys, &bls_secrets);
+        let mut tracker = FinalityTracker::new(&chain_spec, genesis.clone()).unwrap();
+
+        // attestations start at block 3 and skip blocks 6 and 7;
+        // (block, justified, finalized) after each header
+        let expected = [
+            (1, None, None),
+            (2, None, None),
+            (3, Some(2), Some(0)),
+            (4, Some(3), Some(2)),
+            (5, Some(4), Some(3)),
+            (6, Some(4), Some(3)),
+            (7, Some(4), Some(3)),
+            (8, Some(7), Some(3)),
+            (9, Some(8), Some(7)),
+            (10, Some(9), Some(8)),
+            (11, Some(10), Some(9)),
+            (12, Some(11), Some(10)),
+        ];
+        let mut parent = genesis.clone();
+        for (number, justified, finalized) in expected {
+            let snap = tracker.verifier.snapshot().clone();
+            let signer = snap.inturn_validator();
+            let mut header = next_header(&parent, &snap, &chain_spec, signer, &keys[&signer]);
+            if number >= 3 && !(6..=7).contains(&number) {
+                let (source_number, source_hash) = snap.justified().unwrap_or((BlockNumber(0), genesis.hash()));
+                let data = VoteData {
+                    source_number: source_number.0,
+                    source_hash,
+                    target_number: parent.number.0,
+                    target_hash: parent.hash(),
+                };
+                set_attestation(&mut header, &chain_spec, &keys[&signer], &attest(&bls_secrets, 0b011, data));
+            }
+            let finality = tracker.push(&header).unwrap();
+            assert_eq!(justified, finality.justified.map(|j| j.0 .0), "justified at {}", number);
+            assert_eq!(finalized, finality.finalized.map(|f| f.0 .0), "finalized at {}", number);
+            parent = header;
+        }
+        assert_eq!(Some((BlockNumber(11), parent.parent_hash)), tracker.finality().justified);
+    }
+
+    #[test]
+    fn track_from_boneh_checkpoint() {
+        let mut chain_spec = chain_spec(3, 10);
+        chain_spec.upgrades.boneh = Some(BlockNumber(0));
+        let keys = validator_keys(3);
+        let bls_secrets = bls_keys(3);
+        let mut genesis = genesis_header(None);
+        genesis.extra_data = boneh_extra_data(&keys, &bls_secrets);
+        let mut tracker = FinalityTracker::new(&chain_spec, genesis.clone()).unwrap();
+
+        // attested chain up to the epoch header 10
+        let mut parent = genesis.clone();
+        let attested = |tracker: &FinalityTracker, parent: &BlockHeader, source: Option<(BlockNumber, H256)>| {
+            let snap = tracker.verifier.snapshot().clone();
+            let signer = snap.inturn_validator();
+            let mut header = next_header(parent, &snap, &chain_spec, signer, &keys[&signer]);
+            let (source_number, source_hash) = source.or(snap.justified()).unwrap_or((BlockNumber(0), genesis.hash()));
+            let data = VoteData {
+                source_number: source_number.0,
+                source_hash,
+                target_number: parent.number.0,
+                target_hash: parent.hash(),
+            };
+            set_attestation(&mut header, &chain_spec, &keys[&signer], &attest(&bls_secrets, 0b011, data));
+            header
+        };
+        while parent.number.0 < 10 {
+            let header = attested(&tracker, &parent, None);
+            tracker.push(&header).unwrap();
+            parent = header;
+        }
+        let checkpoint = parent;
+        let next = attested(&tracker, &checkpoint, None);
+
+        // the checkpoint's own attestation tells the justified and finalized blocks
+        let mut tracker = FinalityTracker::new(&chain_spec, checkpoint.clone()).unwrap();
+        let finality = tracker.finality();
+        assert_eq!(Some((BlockNumber(9), checkpoint.parent_hash)), finality.justified);
+        assert_eq!(Some(8), finality.finalized.map(|f| f.0 .0));
+
+        let wrong_source = attested(&tracker, &checkpoint, Some((BlockNumber(0), genesis.hash())));
+        assert!(matches!(tracker.push(&wrong_source), Err(ParliaError::WrongAttestationSource { .. })));
+        let finality = tracker.push(&next).unwrap();
+        assert_eq!(Some((BlockNumber(10), checkpoint.hash())), finality.justified);
+        assert_eq!(Some(9), finality.finalized.map(|f| f.0 .0));
+    }
+
+    #[test]
+    fn reject_insufficient_votes() {
+        let mut chain_spec = chain_spec(3, 10);
+        chain_spec.upgrades.boneh = Some(BlockNumber(0));
+        let keys = validator_keys(3);
+        let bls_secrets = bls_keys(3);
+        let mut genesis = genesis_header(None);
+        genesis.extra_data = boneh_extra_data(&keys, &bls_secrets);
+        let mut tracker = FinalityTracker::new(&chain_spec, genesis.clone()).unwrap();
+        let snap = tracker.verifier.snapshot().clone();
+        let signer = snap.inturn_validator();
+
+        let data = VoteData {
+            source_number: 0,
+            source_hash: genesis.hash(),
+            target_number: 0,
+            target_hash: genesis.hash(),
+        };
+        let mut header = next_header(&genesis, &snap, &chain_spec, signer, &keys[&signer]);
+        set_attestation(&mut header, &chain_spec, &keys[&signer], &attest(&bls_secrets, 0b100, data));
+        assert!(matches!(tracker.push(&header), Err(ParliaError::InsufficientVotes { votes: 1, validators: 3, .. })));
+
+        let wrong_source = VoteData { source_number: 1, ..data };
+        set_attestation(&mut header, &chain_spec, &keys[&signer], &attest(&bls_secrets, 0b111, wrong_source));
+        assert!(matches!(tracker.push(&header), Err(ParliaError::WrongAttestationSource { .. })));
+
+        set_attestation(&mut header, &chain_spec, &keys[&signer], &attest(&bls_secrets, 0b111, data));
+        assert_eq!(Some((BlockNumber(0), genesis.hash())), tracker.push(&header).unwrap().justified);
+    }
+}
@@ -0,0 +1,340 @@
+pub mod finality;
+pub mod rng;
+pub mod simulator;
+pub mod snapshot;
+pub mod turn;
+pub mod util;
+pub mod verifier;
+
+pub use finality::*;
+pub use simulator::*;
+pub use snapshot::*;
+pub use turn::*;
+pub use util::*;
+pub use verifier::*;
+
+use crate::models::chainspec::{BlockNumber, BlockScore};
+use ethereum_types::{Address, H256, U256};
+
+/// Epoch length geth takes when the chain config sets none
+pub const DEFAULT_EPOCH_LENGTH: u64 = 200;
+/// Fixed delay before an out-of-turn validator may seal after Ramanujan, in seconds
+pub const INITIAL_BACK_OFF_TIME: u64 = 1;
+/// Extra delay per back off step of an out-of-turn validator after Ramanujan, in seconds
+pub const WIGGLE_TIME: u64 = 1;
+/// Gas limit of a block may change by at most 1/GAS_LIMIT_BOUND_DIVISOR of its parent's
+pub const GAS_LIMIT_BOUND_DIVISOR: u64 = 256;
+/// Minimum the gas limit may ever be
+pub const MIN_GAS_LIMIT: u64 = 5000;
+/// Maximum the gas limit may ever be, 2^63-1
+pub const MAX_GAS_LIMIT: u64 = 0x7fffffffffffffff;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParliaError {
+    NotParliaChain,
+    ZeroEpoch,
+    UnknownAncestor {
+        number: BlockNumber,
+        parent_hash: H256,
+    },
+    WrongHeaderExtraLen {
+        expected: usize,
+        got: usize,
+    },
+    WrongHeaderExtraSignersLen {
+        expected: usize,
+        got: usize,
+    },
+    ExtraValidators {
+        number: BlockNumber,
+    },
+    UnsortedValidators {
+        number: BlockNumber,
+    },
+    TooManyValidators {
+        count: usize,
+    },
+    NotEpochHeader {
+        number: BlockNumber,
+        epoch: u64,
+    },
+    WrongHeaderHash {
+        number: BlockNumber,
+        expected: H256,
+        got: H256,
+    },
+    WrongHeaderSignature {
+        number: BlockNumber,
+    },
+    WrongMixDigest {
+        number: BlockNumber,
+    },
+    WrongUncleHash {
+        number: BlockNumber,
+    },
+    WrongGasLimit {
+        number: BlockNumber,
+        parent: u64,
+        got: u64,
+    },
+    WrongGasUsed {
+        number: BlockNumber,
+        gas_limit: u64,
+        got: u64,
+    },
+    WrongBaseFee {
+        number: BlockNumber,
+        got: Option<U256>,
+    },
+    WrongHeaderTime {
+        number: BlockNumber,
+        expected: u64,
+        got: u64,
+    },
+    CoinbaseMismatch {
+        number: BlockNumber,
+        coinbase: Address,
+        signer: Address,
+    },
+    SignerUnauthorized {
+        number: BlockNumber,
+        signer: Address,
+    },
+    SignerOverLimit {
+        number: BlockNumber,
+        signer: Address,
+    },
+    WrongDifficulty {
+        number: BlockNumber,
+        expected: BlockScore,
+        got: U256,
+    },
+    InvalidAttestation {
+        number: BlockNumber,
+    },
+    WrongAttestationTarget {
+        number: BlockNumber,
+        expected: H256,
+        got: H256,
+    },
+    WrongAttestationSource {
+        number: BlockNumber,
+        source: BlockNumber,
+        hash: H256,
+    },
+    InsufficientVotes {
+        number: BlockNumber,
+        votes: usize,
+        validators: usize,
+    },
+    WrongAttestationSignature {
+        number: BlockNumber,
+    },
+    InvalidBLSPublicKey {
+        validator: Address,
+        err: blst::BLST_ERROR,
+    },
+    DuplicateBLSPublicKey {
+        validator: Address,
+        other: Address,
+    },
+}
+
+impl std::fmt::Display for ParliaError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for ParliaError {}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::{
+        crypto::*,
+        models::{bls::BLSPublicKey, chainspec::*, header::BlockHeader, vote::*},
+    };
+    use fastrlp::Encodable;
+    use bytes::Bytes;
+    use ethereum_types::{Bloom, H64};
+    use secp256k1::SecretKey;
+    use std::collections::BTreeMap;
+
+    pub(crate) fn chain_spec(period: u64, epoch: u64) -> ChainSpec {
+        ChainSpec {
+            name: String::from("parlia-test"),
+            consensus: ConsensusParams {
+                seal_verification: SealVerificationParams::Parlia { period, epoch },
+                eip1559_block: None,
+            },
+            upgrades: Upgrades {
+                ramanujan: Some(BlockNumber(0)),
+                ..Default::default()
+            },
+            params: Params {
+                chain_id: ChainId(714),
+                network_id: NetworkId(714),
+                additional_forks: Default::default(),
+                eip1559: None,
+            },
+            genesis: Genesis {
+                number: BlockNumber(0),
+                parent_hash: H256::zero(),
+                author: Address::zero(),
+                gas_limit: 30_000_000,
+                gas_used: 0,
+                timestamp: 0,
+                seal: Seal::Unknown,
+                base_fee_per_gas: None,
+            },
+            contracts: Default::default(),
+            balances: Default::default(),
+            p2p: P2PParams::default(),
+        }
+    }
+
+    /// Validator keys by address, so that they iterate in turn order.
+    pub(crate) fn validator_keys(count: u8) -> BTreeMap<Address, SecretKey> {
+        (1..=count)
+            .map(|i| {
+                let secret = SecretKey::from_slice(&[i; 32]).unwrap();
+                let public = secp256k1::PublicKey::from_secret_key(secp256k1::SECP256K1, &secret);
+                (public_to_address(&public), secret)
+            })
+            .collect()
+    }
+
+    /// BLS vote keys of the validators of validator_keys, in the same order.
+    pub(crate) fn bls_keys(count: u8) -> Vec<blst::min_pk::SecretKey> {
+        (1..=count)
+            .map(|i| blst::min_pk::SecretKey::key_gen(&[i; 32], &[]).unwrap())
+            .collect()
+    }
+
+    pub(crate) fn boneh_extra_data(
+        keys: &BTreeMap<Address, SecretKey>,
+        bls_secrets: &[blst::min_pk::SecretKey],
+    ) -> Bytes {
+        let validators: Vec<Address> = keys.keys().copied().collect();
+        let bls_pub_keys: Vec<BLSPublicKey> = bls_secrets.iter().map(bls_public).collect();
+        encode_epoch_extra_data(H256::zero(), &validators, Some(&bls_pub_keys)).unwrap().into()
+    }
+
+    /// Attestation of `data` by the validators in the bitset.
+    pub(crate) fn attest(
+        bls_secrets: &[blst::min_pk::SecretKey],
+        voters: ValidatorsBitSet,
+        data: VoteData,
+    ) -> VoteAttestation {
+        let mut attestation = VoteAttestation {
+            vote_address_set: voters,
+            agg_signature: Default::default(),
+            data,
+            extra: Bytes::new(),
+        };
+        let sigs: Vec<_> = attestation
+            .voter_indexes()
+            .map(|i| bls_sign(&bls_secrets[i], data.hash().as_bytes()))
+            .collect();
+        attestation.agg_signature = bls_aggregate(&sigs).unwrap();
+        attestation
+    }
+
+    /// Replaces the attestation in a Boneh header's extraData and seals it again.
+    pub(crate) fn set_attestation(
+        header: &mut BlockHeader,
+        chain_spec: &ChainSpec,
+        secret: &SecretKey,
+        attestation: &VoteAttestation,
+    ) {
+        let prefix_len = if header.number.0 % chain_spec.consensus.parlia_epoch().unwrap() == 0 {
+            let count = header.extra_data[EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH - 1] as usize;
+            EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH + count * EXTRA_VALIDATOR_LEN_IN_BONEH
+        } else {
+            EXTRA_VANITY_LEN
+        };
+        let mut extra_data = header.extra_data[..prefix_len].to_vec();
+        attestation.encode(&mut extra_data);
+        extra_data.extend_from_slice(&[0u8; EXTRA_SEAL_LEN]);
+        header.extra_data = extra_data.into();
+        seal(header, chain_spec, secret);
+    }
+
+    pub(crate) fn seal(header: &mut BlockHeader, chain_spec: &ChainSpec, secret: &SecretKey) {
+        let sig = sign(header.parlia_seal_hash(chain_spec.params.chain_id), secret).unwrap();
+        let mut extra_data = header.extra_data.to_vec();
+        let extra_len = extra_data.len();
+        extra_data[extra_len - EXTRA_SEAL_LEN..].copy_from_slice(&sig);
+        header.extra_data = extra_data.into();
+    }
+
+    pub(crate) fn epoch_extra_data(validators: impl IntoIterator<Item = Address>) -> Bytes {
+        let mut extra_data = vec![0u8; EXTRA_VANITY_LEN];
+        for validator in validators {
+            extra_data.extend_from_slice(validator.as_bytes());
+        }
+        extra_data.extend_from_slice(&[0u8; EXTRA_SEAL_LEN]);
+        extra_data.into()
+    }
+
+    pub(crate) fn genesis_header(validators: impl IntoIterator<Item = Address>) -> BlockHeader {
+        BlockHeader {
+            parent_hash: H256::zero(),
+            ommers_hash: EMPTY_LIST_HASH,
+            beneficiary: Address::zero(),
+            state_root: H256::zero(),
+            transactions_root: H256::zero(),
+            receipts_root: H256::zero(),
+            logs_bloom: Bloom::zero(),
+            difficulty: U256::one(),
+            number: BlockNumber(0),
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            timestamp: 1_600_000_000,
+            extra_data: epoch_extra_data(validators),
+            mix_hash: H256::zero(),
+            nonce: H64::zero(),
+            base_fee_per_gas: None,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            requests_hash: None,
+        }
+    }
+
+    /// Child of `parent` sealed by `signer` at the earliest time it is allowed to.
+    pub(crate) fn next_header(
+        parent: &BlockHeader,
+        snap: &Snapshot,
+        chain_spec: &ChainSpec,
+        signer: Address,
+        secret: &SecretKey,
+    ) -> BlockHeader {
+        let number = parent.number + 1u64;
+        let extra_data = if number.0 % snap.epoch == 0 {
+            match snap.vote_keys().filter(|_| chain_spec.is_boneh(&number)) {
+                Some(bls_pub_keys) => {
+                    encode_epoch_extra_data(H256::zero(), &snap.validators, Some(&bls_pub_keys)).unwrap().into()
+                }
+                None => epoch_extra_data(snap.validators.iter().copied()),
+            }
+        } else {
+            epoch_extra_data(None)
+        };
+        let mut header = BlockHeader {
+            parent_hash: parent.hash(),
+            beneficiary: signer,
+            difficulty: U256::from(snap.block_score(&signer) as u64),
+            number,
+            timestamp: parent
+                .timestamp
+                .saturating_add(chain_spec.consensus.parlia_period().unwrap() + snap.back_off_time(&signer)),
+            extra_data,
+            ..parent.clone()
+        };
+        seal(&mut header, chain_spec, secret);
+        header
+    }
+}
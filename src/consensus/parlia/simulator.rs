@@ -0,0 +1,221 @@
+use super::*;
+use crate::models::{bls::*, chainspec::*, header::BlockHeader};
+use bytes::Bytes;
+use ethereum_types::{Bloom, H256, H64};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Validator that does not seal from block `from` to block `to`, both included.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OfflineWindow {
+    pub validator: Address,
+    pub from: BlockNumber,
+    pub to: BlockNumber,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scenario {
+    #[serde(default)]
+    pub offline: Vec<OfflineWindow>,
+}
+
+impl Scenario {
+    pub fn is_offline(&self, validator: &Address, number: BlockNumber) -> bool {
+        self.offline
+            .iter()
+            .any(|w| w.validator == *validator && w.from <= number && number <= w.to)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SimulatedBlock {
+    pub number: BlockNumber,
+    pub timestamp: u64,
+    pub sealer: Address,
+    pub score: BlockScore,
+    pub inturn: Address,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorStats {
+    pub sealed: u64,
+    pub inturn_slots: u64,
+    pub missed_inturn: u64,
+    /// blocks the validator was online for but could not seal as it sealed too recently
+    pub lockouts: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Simulation {
+    pub blocks: Vec<SimulatedBlock>,
+    pub validators: BTreeMap<Address, ValidatorStats>,
+    /// first block no validator could seal, if any
+    pub halted_at: Option<BlockNumber>,
+}
+
+/// simulate seals `blocks` blocks after genesis with the genesis validators, every block
+/// going to the online validator allowed to seal it the earliest. The validator set is
+/// kept from genesis on, as there is no ValidatorSet contract to elect a new one.
+///
+/// Before Ramanujan all validators may seal `period` after the parent, geth then delays
+/// out-of-turn validators randomly; the model breaks ties with the Ramanujan back off
+/// order instead, so that runs are reproducible.
+pub fn simulate(chain_spec: &ChainSpec, scenario: &Scenario, blocks: u64) -> Result<Simulation, ParliaError> {
+    let period = chain_spec.consensus.parlia_period().ok_or(ParliaError::NotParliaChain)?;
+    let epoch = parlia_epoch(chain_spec)?;
+    let (signers, bls_pub_keys) = match &chain_spec.genesis.seal {
+        Seal::Parlia { signers, bls_pub_keys, .. } => (signers.clone(), bls_pub_keys.clone()),
+        _ => return Err(ParliaError::NotParliaChain),
+    };
+
+    let mut parent = BlockHeader {
+        parent_hash: H256::zero(),
+        ommers_hash: EMPTY_LIST_HASH,
+        beneficiary: chain_spec.genesis.author,
+        state_root: H256::zero(),
+        transactions_root: H256::zero(),
+        receipts_root: H256::zero(),
+        logs_bloom: Bloom::zero(),
+        difficulty: U256::from(BlockScore::NoTurn as u64),
+        number: chain_spec.genesis.number,
+        gas_limit: chain_spec.genesis.gas_limit,
+        gas_used: 0,
+        timestamp: chain_spec.genesis.timestamp,
+        extra_data: Bytes::new(),
+        mix_hash: H256::zero(),
+        nonce: H64::zero(),
+        base_fee_per_gas: None,
+        withdrawals_root: None,
+        blob_gas_used: None,
+        excess_blob_gas: None,
+        parent_beacon_block_root: None,
+        requests_hash: None,
+    };
+    let mut snap = Snapshot::new(epoch, parent.number, parent.hash(), signers, bls_pub_keys);
+
+    let mut simulation = Simulation {
+        validators: snap.validators.iter().map(|v| (*v, ValidatorStats::default())).collect(),
+        ..Default::default()
+    };
+    for _ in 0..blocks {
+        let number = parent.number + 1u64;
+        let is_ramanujan = chain_spec.is_ramanujan(&number);
+        let inturn = snap.inturn_validator();
+        simulation.validators.entry(inturn).or_default().inturn_slots += 1;
+
+        let mut sealer = None;
+        for validator in &snap.validators {
+            if scenario.is_offline(validator, number) {
+                continue;
+            }
+            if snap.sign_recently(validator, number) {
+                simulation.validators.entry(*validator).or_default().lockouts += 1;
+                continue;
+            }
+            let back_off_time = snap.back_off_time(validator);
+            let timestamp = parent.timestamp + period + if is_ramanujan { back_off_time } else { 0 };
+            if sealer.map_or(true, |(t, b, _)| (timestamp, back_off_time) < (t, b)) {
+                sealer = Some((timestamp, back_off_time, *validator));
+            }
+        }
+        let (timestamp, _, sealer) = match sealer {
+            Some(sealer) => sealer,
+            None => {
+                simulation.halted_at = Some(number);
+                break;
+            }
+        };
+        if sealer != inturn {
+            simulation.validators.entry(inturn).or_default().missed_inturn += 1;
+        }
+        simulation.validators.entry(sealer).or_default().sealed += 1;
+
+        let score = snap.block_score(&sealer);
+        let extra_data = if number.0 % epoch == 0 {
+            let bls_pub_keys = chain_spec.is_boneh(&number).then(|| {
+                snap.validators
+                    .iter()
+                    .map(|v| snap.vote_addrs.get(v).copied().unwrap_or_default())
+                    .collect::<Vec<BLSPublicKey>>()
+            });
+            encode_epoch_extra_data(H256::zero(), &snap.validators, bls_pub_keys.as_deref())?
+        } else {
+            encode_epoch_extra_data(H256::zero(), &[], None)?
+        };
+        let header = BlockHeader {
+            parent_hash: snap.hash,
+            beneficiary: sealer,
+            difficulty: U256::from(score as u64),
+            number,
+            timestamp,
+            extra_data: extra_data.into(),
+            ..parent
+        };
+        snap = snap.apply(&header, sealer, chain_spec)?;
+        simulation.blocks.push(SimulatedBlock {
+            number,
+            timestamp,
+            sealer,
+            score,
+            inturn,
+        });
+        parent = header;
+    }
+    Ok(simulation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::*, *};
+    use ethereum_types::H520;
+
+    fn parlia_chain_spec(validators: usize) -> ChainSpec {
+        let mut chain_spec = chain_spec(3, 10);
+        chain_spec.genesis.seal = Seal::Parlia {
+            vanity: H256::zero(),
+            score: BlockScore::NoTurn,
+            signers: validator_keys(validators as u8).into_keys().collect(),
+            bls_pub_keys: None,
+            mix_hash: H256::zero(),
+            nonce: H64::zero(),
+            seal: H520::zero(),
+        };
+        chain_spec
+    }
+
+    #[test]
+    fn simulate_all_online() {
+        let chain_spec = parlia_chain_spec(5);
+        let simulation = simulate(&chain_spec, &Scenario::default(), 30).unwrap();
+
+        assert_eq!(30, simulation.blocks.len());
+        assert!(simulation.blocks.iter().all(|b| b.sealer == b.inturn && b.score == BlockScore::InTurn));
+        assert!(simulation.blocks.windows(2).all(|w| w[1].timestamp == w[0].timestamp + 3));
+        assert!(simulation.validators.values().all(|s| s.sealed == 6 && s.missed_inturn == 0));
+    }
+
+    #[test]
+    fn simulate_offline_validators() {
+        let chain_spec = parlia_chain_spec(5);
+        let validators: Vec<Address> = validator_keys(5).into_keys().collect();
+        let scenario = Scenario {
+            offline: vec![OfflineWindow { validator: validators[0], from: BlockNumber(1), to: BlockNumber(20) }],
+        };
+        let simulation = simulate(&chain_spec, &scenario, 20).unwrap();
+
+        assert_eq!(None, simulation.halted_at);
+        assert_eq!(0, simulation.validators[&validators[0]].sealed);
+        assert_eq!(4, simulation.validators[&validators[0]].missed_inturn);
+        assert!(simulation.blocks.iter().any(|b| b.score == BlockScore::NoTurn));
+
+        // three validators out of five can not take turns under the recent signer rule
+        let scenario = Scenario {
+            offline: validators[..3]
+                .iter()
+                .map(|v| OfflineWindow { validator: *v, from: BlockNumber(10), to: BlockNumber(20) })
+                .collect(),
+        };
+        let simulation = simulate(&chain_spec, &scenario, 20).unwrap();
+        assert!(simulation.halted_at.is_some());
+    }
+}
@@ -0,0 +1,216 @@
+use super::*;
+use crate::models::{bls::*, chainspec::*, header::BlockHeader, vote::VoteData};
+use ethereum_types::{Address, H256};
+use std::collections::BTreeMap;
+
+/// Snapshot is the state of the Parlia validator set at a given block, as geth keeps it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    pub epoch: u64,
+    pub number: BlockNumber,
+    pub hash: H256,
+    /// validators sorted by address, the order turns and vote bitsets refer to
+    pub validators: Vec<Address>,
+    pub vote_addrs: BTreeMap<Address, BLSPublicKey>,
+    /// validators that sealed the latest blocks, by block number
+    pub recents: BTreeMap<BlockNumber, Address>,
+    /// validators of the last epoch header, they take over once it is half a set deep
+    pub epoch_validators: Option<EpochValidators>,
+    /// latest vote attestation for a parent block, its target is the justified block and
+    /// its source the finalized one
+    pub attestation: Option<VoteData>,
+}
+
+impl Snapshot {
+    pub fn new(
+        epoch: u64,
+        number: BlockNumber,
+        hash: H256,
+        validators: Vec<Address>,
+        bls_pub_keys: Option<Vec<BLSPublicKey>>,
+    ) -> Self {
+        let mut snap = Self {
+            epoch,
+            number,
+            hash,
+            validators: Vec::new(),
+            vote_addrs: BTreeMap::new(),
+            recents: BTreeMap::new(),
+            epoch_validators: None,
+            attestation: None,
+        };
+        snap.set_validators(validators, bls_pub_keys);
+        snap
+    }
+
+    /// Snapshot right after a trusted epoch header, e.g. genesis or a checkpoint, taking
+    /// the validators from its extraData the way geth does for its trusted checkpoints.
+    /// After Boneh the attestation of the header gives the justified and finalized blocks.
+    pub fn from_epoch_header(header: &BlockHeader, chain_spec: &ChainSpec) -> Result<Self, ParliaError> {
+        let epoch = parlia_epoch(chain_spec)?;
+        if header.number.0 % epoch != 0 {
+            return Err(ParliaError::NotEpochHeader { number: header.number, epoch });
+        }
+        let is_boneh = chain_spec.is_boneh(&header.number);
+        let (validators, bls_pub_keys) = parse_epoch_validators(&header.extra_data, is_boneh)?;
+        let mut snap = Self::new(epoch, header.number, header.hash(), validators, bls_pub_keys);
+        if let Some(attestation) = parse_vote_attestation(header, epoch, is_boneh)? {
+            snap.update_attestation(header, &attestation.data);
+        }
+        Ok(snap)
+    }
+
+    fn set_validators(&mut self, mut validators: Vec<Address>, bls_pub_keys: Option<Vec<BLSPublicKey>>) {
+        self.vote_addrs = bls_pub_keys
+            .map(|keys| validators.iter().copied().zip(keys).collect())
+            .unwrap_or_default();
+        validators.sort();
+        validators.dedup();
+        self.validators = validators;
+    }
+
+    pub fn index_of(&self, validator: &Address) -> Option<usize> {
+        self.validators.binary_search(validator).ok()
+    }
+
+    /// Validator in turn to seal the block after this snapshot.
+    pub fn inturn_validator(&self) -> Address {
+        inturn_validator(&self.validators, self.number + 1u64)
+    }
+
+    pub fn is_inturn(&self, validator: &Address) -> bool {
+        self.inturn_validator() == *validator
+    }
+
+    /// BLS vote keys of the validators in address order, the order of attestation bitsets,
+    /// None if one of them has no key.
+    pub fn vote_keys(&self) -> Option<Vec<BLSPublicKey>> {
+        self.validators.iter().map(|v| self.vote_addrs.get(v).copied()).collect()
+    }
+
+    /// Difficulty the validator has to seal the next block with.
+    pub fn block_score(&self, validator: &Address) -> BlockScore {
+        block_score(&self.validators, self.number + 1u64, validator)
+    }
+
+    /// Number of consecutive blocks a validator may seal at most one of.
+    pub fn recents_limit(&self) -> u64 {
+        (self.validators.len() / 2 + 1) as u64
+    }
+
+    /// Whether the validator sealed one of the last blocks and may not seal block `number`;
+    /// before `recents_limit` blocks every block counts, as in geth.
+    pub fn sign_recently(&self, validator: &Address, number: BlockNumber) -> bool {
+        self.recents.iter().any(|(seen, recent)| {
+            recent == validator
+                && number
+                    .0
+                    .checked_sub(self.recents_limit())
+                    .map_or(true, |limit| seen.0 > limit)
+        })
+    }
+
+    /// Seconds the validator has to wait after `period` to seal the next block, once Ramanujan is active.
+    pub fn back_off_time(&self, validator: &Address) -> u64 {
+        back_off_time(&self.validators, self.number + 1u64, validator)
+    }
+
+    /// Latest justified block, None until an attestation was seen, the anchor's included.
+    pub fn justified(&self) -> Option<(BlockNumber, H256)> {
+        self.attestation
+            .map(|a| (BlockNumber(a.target_number), a.target_hash))
+    }
+
+    /// Latest finalized block, None until an attestation was seen, the anchor's included.
+    pub fn finalized(&self) -> Option<(BlockNumber, H256)> {
+        self.attestation
+            .map(|a| (BlockNumber(a.source_number), a.source_hash))
+    }
+
+    /// An attestation for the parent justifies it, and finalizes its source if that is the
+    /// grandparent; otherwise the finalized block stays the same.
+    fn update_attestation(&mut self, header: &BlockHeader, data: &VoteData) {
+        if data.target_hash != header.parent_hash || data.target_number + 1 != header.number.0 {
+            return;
+        }
+        match &mut self.attestation {
+            Some(attestation) if data.source_number + 1 != data.target_number => {
+                attestation.target_number = data.target_number;
+                attestation.target_hash = data.target_hash;
+            }
+            attestation => *attestation = Some(*data),
+        }
+    }
+
+    /// apply moves the snapshot to the next header, sealed by `signer`.
+    pub fn apply(
+        &self,
+        header: &BlockHeader,
+        signer: Address,
+        chain_spec: &ChainSpec,
+    ) -> Result<Self, ParliaError> {
+        let number = header.number;
+        if number != self.number + 1u64 || header.parent_hash != self.hash {
+            return Err(ParliaError::UnknownAncestor {
+                number,
+                parent_hash: header.parent_hash,
+            });
+        }
+
+        let mut snap = self.clone();
+        if let Some(expired) = number.0.checked_sub(snap.recents_limit()) {
+            snap.recents.remove(&BlockNumber(expired));
+        }
+        if snap.index_of(&signer).is_none() {
+            return Err(ParliaError::SignerUnauthorized { number, signer });
+        }
+        if snap.recents.values().any(|recent| *recent == signer) {
+            return Err(ParliaError::SignerOverLimit { number, signer });
+        }
+        snap.recents.insert(number, signer);
+
+        let is_boneh = chain_spec.is_boneh(&number);
+        if let Some(attestation) = parse_vote_attestation(header, snap.epoch, is_boneh)? {
+            snap.update_attestation(header, &attestation.data);
+        }
+
+        if number.0 % snap.epoch == 0 {
+            snap.epoch_validators = Some(parse_epoch_validators(&header.extra_data, is_boneh)?);
+        }
+        if number.0 > 0 && number.0 % snap.epoch == (snap.validators.len() / 2) as u64 {
+            if let Some((validators, bls_pub_keys)) = snap.epoch_validators.take() {
+                let old_limit = snap.recents_limit();
+                let new_limit = (validators.len() / 2 + 1) as u64;
+                for i in 0..old_limit.saturating_sub(new_limit) {
+                    if let Some(expired) = number.0.checked_sub(new_limit + i) {
+                        snap.recents.remove(&BlockNumber(expired));
+                    }
+                }
+                snap.set_validators(validators, bls_pub_keys);
+            }
+        }
+
+        snap.number = number;
+        snap.hash = header.hash();
+        Ok(snap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sign_recently_from_genesis() {
+        let validators = (1..=5).map(Address::repeat_byte).collect::<Vec<_>>();
+        let mut snap = Snapshot::new(200, BlockNumber(1), H256::zero(), validators.clone(), None);
+        snap.recents.insert(BlockNumber(1), validators[0]);
+        assert_eq!(3, snap.recents_limit());
+
+        // below the limit every recent block counts, as in geth
+        assert!(snap.sign_recently(&validators[0], BlockNumber(2)));
+        assert!(snap.sign_recently(&validators[0], BlockNumber(3)));
+        assert!(!snap.sign_recently(&validators[1], BlockNumber(2)));
+        assert!(!snap.sign_recently(&validators[0], BlockNumber(4)));
+    }
+}
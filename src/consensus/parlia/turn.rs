@@ -0,0 +1,119 @@
+use super::{rng::GoRng, *};
+use crate::models::chainspec::*;
+use ethereum_types::Address;
+
+// All the functions below take the validators sorted by address, as geth orders them.
+
+/// Validator in turn to seal block `number`.
+pub fn inturn_validator(validators: &[Address], number: BlockNumber) -> Address {
+    validators[(number.0 % validators.len() as u64) as usize]
+}
+
+/// Difficulty `validator` has to seal block `number` with.
+pub fn block_score(validators: &[Address], number: BlockNumber, validator: &Address) -> BlockScore {
+    if inturn_validator(validators, number) == *validator {
+        BlockScore::InTurn
+    } else {
+        BlockScore::NoTurn
+    }
+}
+
+/// Seconds `validator` has to wait after `period` to seal block `number` once Ramanujan
+/// is active: nothing when in turn, otherwise a fixed delay plus its step in a shuffle
+/// seeded by the parent number.
+pub fn back_off_time(validators: &[Address], number: BlockNumber, validator: &Address) -> u64 {
+    if inturn_validator(validators, number) == *validator {
+        return 0;
+    }
+    let idx = match validators.binary_search(validator) {
+        Ok(idx) => idx,
+        // the back off time does not matter when a validator is not authorized
+        Err(_) => return 0,
+    };
+
+    let mut back_off_steps: Vec<u64> = (0..validators.len() as u64).collect();
+    GoRng::new(number.0 as i64 - 1).shuffle(&mut back_off_steps);
+    INITIAL_BACK_OFF_TIME + back_off_steps[idx] * WIGGLE_TIME
+}
+
+/// When and how a validator may seal a block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SealTurn {
+    pub validator: Address,
+    pub score: BlockScore,
+    pub back_off_time: u64,
+    /// earliest timestamp the block may carry
+    pub earliest_timestamp: u64,
+}
+
+/// seal_turns lists, for every validator, the difficulty and the earliest legal timestamp
+/// of block `number` given its parent timestamp. Before Ramanujan every validator may
+/// seal `period` seconds after the parent.
+pub fn seal_turns(
+    validators: &[Address],
+    number: BlockNumber,
+    parent_timestamp: u64,
+    period: u64,
+    is_ramanujan: bool,
+) -> Vec<SealTurn> {
+    validators
+        .iter()
+        .map(|validator| {
+            let back_off_time = if is_ramanujan {
+                back_off_time(validators, number, validator)
+            } else {
+                0
+            };
+            SealTurn {
+                validator: *validator,
+                score: block_score(validators, number, validator),
+                back_off_time,
+                earliest_timestamp: parent_timestamp + period + back_off_time,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn back_off_time_known_answers() {
+        // geth's backOffTime for every validator, the in-turn one waiting for nothing
+        let validators: Vec<Address> = (1..=21u8).map(Address::repeat_byte).collect();
+        let expected = [9, 15, 0, 6, 14, 21, 5, 2, 1, 18, 19, 3, 8, 16, 20, 17, 10, 4, 7, 11, 13];
+        let got = validators.iter().map(|v| back_off_time(&validators, BlockNumber(1000001), v)).collect::<Vec<_>>();
+        assert_eq!(expected.to_vec(), got);
+
+        let validators: Vec<Address> = (1..=7u8).map(Address::repeat_byte).collect();
+        let got = validators.iter().map(|v| back_off_time(&validators, BlockNumber(2), v)).collect::<Vec<_>>();
+        assert_eq!(vec![1, 3, 0, 2, 4, 6, 5], got);
+        assert_eq!(0, back_off_time(&validators, BlockNumber(2), &Address::repeat_byte(8)));
+    }
+
+    #[test]
+    fn seal_turns_follow_sorted_validators() {
+        let validators: Vec<Address> = (1..=5u8).map(Address::repeat_byte).collect();
+        let turns = seal_turns(&validators, BlockNumber(7), 100, 3, true);
+
+        let inturn = turns.iter().filter(|t| t.score == BlockScore::InTurn).collect::<Vec<_>>();
+        assert_eq!(1, inturn.len());
+        assert_eq!(validators[2], inturn[0].validator);
+        assert_eq!(103, inturn[0].earliest_timestamp);
+
+        // out-of-turn validators get distinct back off steps
+        let mut back_offs = turns
+            .iter()
+            .filter(|t| t.score == BlockScore::NoTurn)
+            .map(|t| t.back_off_time)
+            .collect::<Vec<_>>();
+        back_offs.sort_unstable();
+        back_offs.dedup();
+        assert_eq!(4, back_offs.len());
+        assert!(back_offs.iter().all(|b| (1..=5).contains(b)));
+
+        let turns = seal_turns(&validators, BlockNumber(7), 100, 3, false);
+        assert!(turns.iter().all(|t| t.earliest_timestamp == 103));
+    }
+}
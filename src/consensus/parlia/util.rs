@@ -0,0 +1,274 @@
+use super::{ParliaError, EMPTY_LIST_HASH, EMPTY_ROOT_HASH};
+use crate::{
+    crypto::*,
+    models::{bls::*, chainspec::*, header::BlockHeader, vote::{VoteAttestation, MAX_ATTESTATION_EXTRA_LEN}},
+};
+use fastrlp::Decodable;
+use ethereum_types::{Address, Bloom, H256, U256};
+use std::collections::BTreeMap;
+
+/// parlia_epoch is the epoch length of a Parlia chain spec, which must not be zero.
+pub fn parlia_epoch(chain_spec: &ChainSpec) -> Result<u64, ParliaError> {
+    match chain_spec.consensus.parlia_epoch() {
+        Some(0) => Err(ParliaError::ZeroEpoch),
+        Some(epoch) => Ok(epoch),
+        None => Err(ParliaError::NotParliaChain),
+    }
+}
+
+/// Validators listed in an epoch header's extraData, with their BLS vote keys after Boneh.
+pub type EpochValidators = (Vec<Address>, Option<Vec<BLSPublicKey>>);
+
+/// parse_epoch_validators decodes the validator set from an epoch header's extraData.
+/// Before Boneh it is a plain list of addresses, after Boneh it is prefixed by the
+/// validator count and every address is followed by its BLS public key.
+pub fn parse_epoch_validators(
+    extra_data: &[u8],
+    is_boneh: bool,
+) -> Result<EpochValidators, ParliaError> {
+    let extra_len = extra_data.len();
+    let min_len = if is_boneh {
+        EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH + EXTRA_SEAL_LEN
+    } else {
+        EXTRA_VANITY_LEN + EXTRA_SEAL_LEN
+    };
+    if extra_len < min_len {
+        return Err(ParliaError::WrongHeaderExtraLen {
+            expected: min_len,
+            got: extra_len,
+        });
+    }
+
+    let mut signers = Vec::new();
+    if !is_boneh {
+        let val_bytes = &extra_data[EXTRA_VANITY_LEN..extra_len - EXTRA_SEAL_LEN];
+        if val_bytes.len() % EXTRA_VALIDATOR_LEN != 0 {
+            return Err(ParliaError::WrongHeaderExtraSignersLen {
+                expected: val_bytes.len() / EXTRA_VALIDATOR_LEN * EXTRA_VALIDATOR_LEN,
+                got: val_bytes.len(),
+            });
+        }
+        for chunk in val_bytes.chunks(EXTRA_VALIDATOR_LEN) {
+            signers.push(Address::from_slice(chunk));
+        }
+        return Ok((signers, None));
+    }
+
+    let count = extra_data[EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH - 1] as usize;
+    let start = EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH;
+    let end = start + count * EXTRA_VALIDATOR_LEN_IN_BONEH;
+    if end > extra_len - EXTRA_SEAL_LEN {
+        return Err(ParliaError::WrongHeaderExtraSignersLen {
+            expected: count * EXTRA_VALIDATOR_LEN_IN_BONEH,
+            got: extra_len - EXTRA_SEAL_LEN - start,
+        });
+    }
+
+    let mut bls_keys = Vec::new();
+    for chunk in extra_data[start..end].chunks(EXTRA_VALIDATOR_LEN_IN_BONEH) {
+        signers.push(Address::from_slice(&chunk[..EXTRA_VALIDATOR_LEN]));
+        bls_keys.push(BLSPublicKey::from_slice(&chunk[EXTRA_VALIDATOR_LEN..]));
+    }
+    Ok((signers, Some(bls_keys)))
+}
+
+/// validate_bls_pub_keys checks the BLS vote keys of a validator set, given in the same
+/// order as the validators: every key must be a valid G1 point and no two validators may
+/// share one, as their votes could not be told apart.
+pub fn validate_bls_pub_keys(
+    validators: &[Address],
+    bls_pub_keys: &[BLSPublicKey],
+) -> Result<(), ParliaError> {
+    let mut seen = BTreeMap::new();
+    for (validator, bls_pub_key) in validators.iter().zip(bls_pub_keys) {
+        bls_validate_public(bls_pub_key)
+            .map_err(|err| ParliaError::InvalidBLSPublicKey { validator: *validator, err })?;
+        if let Some(other) = seen.insert(*bls_pub_key, *validator) {
+            return Err(ParliaError::DuplicateBLSPublicKey { validator: *validator, other });
+        }
+    }
+    Ok(())
+}
+
+/// encode_epoch_extra_data lays out an epoch header's extraData with an empty seal, the
+/// reverse of parse_epoch_validators: BLS keys switch to the Boneh layout, whose one byte
+/// validator count holds 255 at most.
+pub fn encode_epoch_extra_data(
+    vanity: H256,
+    validators: &[Address],
+    bls_pub_keys: Option<&[BLSPublicKey]>,
+) -> Result<Vec<u8>, ParliaError> {
+    let mut extra_data = vanity.as_bytes().to_vec();
+    match bls_pub_keys {
+        None => {
+            for validator in validators {
+                extra_data.extend_from_slice(validator.as_bytes());
+            }
+        }
+        Some(bls_pub_keys) => {
+            let count = u8::try_from(validators.len())
+                .map_err(|_| ParliaError::TooManyValidators { count: validators.len() })?;
+            extra_data.push(count);
+            for (validator, bls_pub_key) in validators.iter().zip(bls_pub_keys) {
+                extra_data.extend_from_slice(validator.as_bytes());
+                extra_data.extend_from_slice(bls_pub_key.as_bytes());
+            }
+        }
+    }
+    extra_data.extend_from_slice(&[0u8; EXTRA_SEAL_LEN]);
+    Ok(extra_data)
+}
+
+/// rebuild_genesis_header rebuilds the genesis header of a Parlia chain spec as geth builds
+/// it from its genesis.json, but for the state root of the genesis allocations, which is
+/// given.
+pub fn rebuild_genesis_header(chain_spec: &ChainSpec, state_root: H256) -> Result<BlockHeader, ParliaError> {
+    let genesis = &chain_spec.genesis;
+    let (vanity, score, signers, bls_pub_keys, mix_hash, nonce, seal) = match &genesis.seal {
+        Seal::Parlia { vanity, score, signers, bls_pub_keys, mix_hash, nonce, seal } => {
+            (vanity, score, signers, bls_pub_keys, mix_hash, nonce, seal)
+        }
+        _ => return Err(ParliaError::NotParliaChain),
+    };
+    let mut extra_data = encode_epoch_extra_data(*vanity, signers, bls_pub_keys.as_deref())?;
+    let seal_start = extra_data.len() - EXTRA_SEAL_LEN;
+    extra_data[seal_start..].copy_from_slice(seal.as_bytes());
+    Ok(BlockHeader {
+        parent_hash: genesis.parent_hash,
+        ommers_hash: EMPTY_LIST_HASH,
+        beneficiary: genesis.author,
+        state_root,
+        transactions_root: EMPTY_ROOT_HASH,
+        receipts_root: EMPTY_ROOT_HASH,
+        logs_bloom: Bloom::zero(),
+        difficulty: U256::from(*score as u64),
+        number: genesis.number,
+        gas_limit: genesis.gas_limit,
+        gas_used: genesis.gas_used,
+        timestamp: genesis.timestamp,
+        extra_data: extra_data.into(),
+        mix_hash: *mix_hash,
+        nonce: *nonce,
+        base_fee_per_gas: genesis.base_fee_per_gas,
+        withdrawals_root: None,
+        blob_gas_used: None,
+        excess_blob_gas: None,
+        parent_beacon_block_root: None,
+        requests_hash: None,
+    })
+}
+
+/// parse_vote_attestation decodes the vote attestation for the parent block that headers
+/// carry after Boneh, between the validators, in epoch headers, and the seal.
+pub fn parse_vote_attestation(
+    header: &BlockHeader,
+    epoch: u64,
+    is_boneh: bool,
+) -> Result<Option<VoteAttestation>, ParliaError> {
+    if !is_boneh {
+        return Ok(None);
+    }
+    let extra_data = &header.extra_data;
+    let extra_len = extra_data.len();
+    let start = if header.number.0 % epoch == 0 {
+        if extra_len < EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH + EXTRA_SEAL_LEN {
+            return Err(ParliaError::WrongHeaderExtraLen {
+                expected: EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH + EXTRA_SEAL_LEN,
+                got: extra_len,
+            });
+        }
+        let count = extra_data[EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH - 1] as usize;
+        EXTRA_VANITY_LEN_WITH_NUM_IN_BONEH + count * EXTRA_VALIDATOR_LEN_IN_BONEH
+    } else {
+        EXTRA_VANITY_LEN
+    };
+    if start + EXTRA_SEAL_LEN > extra_len {
+        return Err(ParliaError::WrongHeaderExtraLen {
+            expected: start + EXTRA_SEAL_LEN,
+            got: extra_len,
+        });
+    }
+
+    let mut buf = &extra_data[start..extra_len - EXTRA_SEAL_LEN];
+    if buf.is_empty() {
+        return Ok(None);
+    }
+    let attestation = VoteAttestation::decode(&mut buf)
+        .map_err(|_| ParliaError::InvalidAttestation { number: header.number })?;
+    // geth decodes the whole span, no bytes may follow the attestation
+    if !buf.is_empty() || attestation.extra.len() > MAX_ATTESTATION_EXTRA_LEN {
+        return Err(ParliaError::InvalidAttestation { number: header.number });
+    }
+    Ok(Some(attestation))
+}
+
+/// recover_creator recovers the validator that sealed the header from the signature
+/// at the end of its extraData.
+pub fn recover_creator(header: &BlockHeader, chain_id: ChainId) -> Result<Address, ParliaError> {
+    let extra_len = header.extra_data.len();
+    if extra_len < EXTRA_VANITY_LEN + EXTRA_SEAL_LEN {
+        return Err(ParliaError::WrongHeaderExtraLen {
+            expected: EXTRA_VANITY_LEN + EXTRA_SEAL_LEN,
+            got: extra_len,
+        });
+    }
+
+    let sig = &header.extra_data[extra_len - EXTRA_SEAL_LEN..];
+    let public = recover_public(sig, header.parlia_seal_hash(chain_id))
+        .map_err(|_| ParliaError::WrongHeaderSignature { number: header.number })?;
+    Ok(public_to_address(&public))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::*, *};
+    use blst::BLST_ERROR;
+
+    #[test]
+    fn validate_bls_keys() {
+        let validators: Vec<Address> = validator_keys(3).into_keys().collect();
+        let mut bls_pub_keys: Vec<BLSPublicKey> = bls_keys(3).iter().map(bls_public).collect();
+        validate_bls_pub_keys(&validators, &bls_pub_keys).unwrap();
+
+        let valid = bls_pub_keys[1];
+        bls_pub_keys[1] = bls_pub_keys[0];
+        assert_eq!(
+            Err(ParliaError::DuplicateBLSPublicKey { validator: validators[1], other: validators[0] }),
+            validate_bls_pub_keys(&validators, &bls_pub_keys)
+        );
+
+        // compressed point at infinity
+        let mut infinity = BLSPublicKey::zero();
+        infinity.0[0] = 0xc0;
+        bls_pub_keys[1] = infinity;
+        assert_eq!(
+            Err(ParliaError::InvalidBLSPublicKey { validator: validators[1], err: BLST_ERROR::BLST_PK_IS_INFINITY }),
+            validate_bls_pub_keys(&validators, &bls_pub_keys)
+        );
+
+        // uncompressed flag on 48 bytes, and an x with no point on the curve
+        bls_pub_keys[1] = BLSPublicKey::repeat_byte(0x11);
+        assert!(validate_bls_pub_keys(&validators, &bls_pub_keys).is_err());
+        bls_pub_keys[1] = valid;
+        bls_pub_keys[1].0[47] ^= 1;
+        assert!(validate_bls_pub_keys(&validators, &bls_pub_keys).is_err());
+    }
+
+    #[test]
+    fn reject_malformed_attestation() {
+        let header = |attestation: &VoteAttestation, trailing: &[u8]| {
+            let mut extra_data = vec![0u8; EXTRA_VANITY_LEN];
+            fastrlp::Encodable::encode(attestation, &mut extra_data);
+            extra_data.extend_from_slice(trailing);
+            extra_data.extend_from_slice(&[0u8; EXTRA_SEAL_LEN]);
+            BlockHeader { number: BlockNumber(201), extra_data: extra_data.into(), ..genesis_header([]) }
+        };
+        let mut attestation = attest(&bls_keys(3), 0b111, Default::default());
+        assert_eq!(Ok(Some(attestation.clone())), parse_vote_attestation(&header(&attestation, &[]), 200, true));
+
+        let err = Err(ParliaError::InvalidAttestation { number: BlockNumber(201) });
+        assert_eq!(err, parse_vote_attestation(&header(&attestation, &[0x80]), 200, true));
+        attestation.extra = vec![0u8; MAX_ATTESTATION_EXTRA_LEN + 1].into();
+        assert_eq!(err, parse_vote_attestation(&header(&attestation, &[]), 200, true));
+    }
+}
@@ -0,0 +1,299 @@
+use super::*;
+use crate::models::{chainspec::*, header::BlockHeader, vote::VoteAttestation};
+use ethereum_types::{H256, U256};
+use hex_literal::hex;
+
+/// keccak256 of the RLP of an empty list, the ommers hash of every Parlia block
+pub const EMPTY_LIST_HASH: H256 = H256(hex!(
+    "1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347"
+));
+
+/// Root of an empty trie, the transactions and receipts root of a genesis header
+pub const EMPTY_ROOT_HASH: H256 = H256(hex!(
+    "56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"
+));
+
+/// HeaderVerifier checks a chain of consecutive headers the way geth's Parlia engine
+/// does, without state: the validator set in epoch headers is checked for its shape only,
+/// since comparing it with the ValidatorSet contract needs the chain state.
+pub struct HeaderVerifier<'a> {
+    chain_spec: &'a ChainSpec,
+    period: u64,
+    parent: BlockHeader,
+    snapshot: Snapshot,
+    /// snapshot before the parent, its validators vote for the parent
+    voters: Snapshot,
+    /// the source of the first attestations, known when starting from genesis
+    genesis_hash: Option<H256>,
+}
+
+impl<'a> HeaderVerifier<'a> {
+    /// Starts verifying the children of a trusted epoch header, e.g. genesis or a checkpoint.
+    pub fn new(chain_spec: &'a ChainSpec, anchor: BlockHeader) -> Result<Self, ParliaError> {
+        let period = chain_spec.consensus.parlia_period().ok_or(ParliaError::NotParliaChain)?;
+        let snapshot = Snapshot::from_epoch_header(&anchor, chain_spec)?;
+        let genesis_hash = (anchor.number.0 == 0).then(|| snapshot.hash);
+        Ok(Self {
+            chain_spec,
+            period,
+            parent: anchor,
+            voters: snapshot.clone(),
+            snapshot,
+            genesis_hash,
+        })
+    }
+
+    pub fn snapshot(&self) -> &Snapshot {
+        &self.snapshot
+    }
+
+    /// verify checks the next header of the chain and, if it is valid, moves on to it.
+    pub fn verify(&mut self, header: &BlockHeader) -> Result<(), ParliaError> {
+        let number = header.number;
+        let parent = &self.parent;
+        if number != parent.number + 1u64 || header.parent_hash != self.snapshot.hash {
+            return Err(ParliaError::UnknownAncestor {
+                number,
+                parent_hash: header.parent_hash,
+            });
+        }
+
+        self.verify_extra_data(header)?;
+        if !header.mix_hash.is_zero() {
+            return Err(ParliaError::WrongMixDigest { number });
+        }
+        if header.ommers_hash != EMPTY_LIST_HASH {
+            return Err(ParliaError::WrongUncleHash { number });
+        }
+
+        // gas limit may only move by 1/256 of the parent's per block
+        if header.gas_limit > MAX_GAS_LIMIT {
+            return Err(ParliaError::WrongGasLimit { number, parent: parent.gas_limit, got: header.gas_limit });
+        }
+        if header.gas_used > header.gas_limit {
+            return Err(ParliaError::WrongGasUsed { number, gas_limit: header.gas_limit, got: header.gas_used });
+        }
+        let diff = parent.gas_limit.abs_diff(header.gas_limit);
+        if diff >= parent.gas_limit / GAS_LIMIT_BOUND_DIVISOR || header.gas_limit < MIN_GAS_LIMIT {
+            return Err(ParliaError::WrongGasLimit { number, parent: parent.gas_limit, got: header.gas_limit });
+        }
+        if self.chain_spec.is_london(&number) != header.base_fee_per_gas.is_some() {
+            return Err(ParliaError::WrongBaseFee { number, got: header.base_fee_per_gas });
+        }
+
+        let snap = &self.snapshot;
+        let signer = recover_creator(header, self.chain_spec.params.chain_id)?;
+        if signer != header.beneficiary {
+            return Err(ParliaError::CoinbaseMismatch { number, coinbase: header.beneficiary, signer });
+        }
+        if snap.index_of(&signer).is_none() {
+            return Err(ParliaError::SignerUnauthorized { number, signer });
+        }
+        if snap.sign_recently(&signer, number) {
+            return Err(ParliaError::SignerOverLimit { number, signer });
+        }
+        let expected = snap.block_score(&signer);
+        if header.difficulty != U256::from(expected as u64) {
+            return Err(ParliaError::WrongDifficulty { number, expected, got: header.difficulty });
+        }
+        // a block comes `period` after its parent at the earliest, out-of-turn validators
+        // back off on top of that after Ramanujan; a time past u64 is never reached
+        let back_off = if self.chain_spec.is_ramanujan(&number) { snap.back_off_time(&signer) } else { 0 };
+        let earliest = parent.timestamp.checked_add(self.period).and_then(|time| time.checked_add(back_off));
+        if earliest.map_or(true, |earliest| header.timestamp < earliest) {
+            return Err(ParliaError::WrongHeaderTime {
+                number,
+                expected: earliest.unwrap_or(u64::MAX),
+                got: header.timestamp,
+            });
+        }
+
+        if let Some(attestation) =
+            parse_vote_attestation(header, snap.epoch, self.chain_spec.is_boneh(&number))?
+        {
+            self.verify_attestation(header, &attestation)?;
+        }
+
+        let snapshot = self.snapshot.apply(header, signer, self.chain_spec)?;
+        self.voters = std::mem::replace(&mut self.snapshot, snapshot);
+        self.parent = header.clone();
+        Ok(())
+    }
+
+    /// The attestation in a header must be for its parent, from the justified block, and
+    /// signed by 2/3 of the validators of the snapshot the parent was sealed on.
+    fn verify_attestation(&self, header: &BlockHeader, attestation: &VoteAttestation) -> Result<(), ParliaError> {
+        let number = header.number;
+        let data = &attestation.data;
+        if data.target_number != self.parent.number.0 || data.target_hash != self.snapshot.hash {
+            return Err(ParliaError::WrongAttestationTarget {
+                number,
+                expected: self.snapshot.hash,
+                got: data.target_hash,
+            });
+        }
+        // before any attestation the source is genesis; after an anchor with no attestation
+        // the justified block is unknown, any earlier block is taken
+        let source_ok = match (self.snapshot.justified(), self.genesis_hash) {
+            (Some((justified, hash)), _) => data.source_number == justified.0 && data.source_hash == hash,
+            (None, Some(hash)) => data.source_number == 0 && data.source_hash == hash,
+            (None, None) => data.source_number < data.target_number,
+        };
+        if !source_ok {
+            return Err(ParliaError::WrongAttestationSource {
+                number,
+                source: BlockNumber(data.source_number),
+                hash: data.source_hash,
+            });
+        }
+
+        let validators = self.voters.validators.len();
+        if attestation.voters(&self.voters.validators).is_none() {
+            return Err(ParliaError::InvalidAttestation { number });
+        }
+        let votes = attestation.vote_address_set.count_ones() as usize;
+        if votes < (validators * 2 + 2) / 3 {
+            return Err(ParliaError::InsufficientVotes { number, votes, validators });
+        }
+        let vote_keys = self.voters.vote_keys().ok_or(ParliaError::InvalidAttestation { number })?;
+        if !attestation.verify(&vote_keys) {
+            return Err(ParliaError::WrongAttestationSignature { number });
+        }
+        Ok(())
+    }
+
+    /// Epoch headers list the next validators sorted by address, other headers list none.
+    /// After Boneh the bytes of other headers hold the vote attestation instead.
+    fn verify_extra_data(&self, header: &BlockHeader) -> Result<(), ParliaError> {
+        let number = header.number;
+        let extra_len = header.extra_data.len();
+        if extra_len < EXTRA_VANITY_LEN + EXTRA_SEAL_LEN {
+            return Err(ParliaError::WrongHeaderExtraLen {
+                expected: EXTRA_VANITY_LEN + EXTRA_SEAL_LEN,
+                got: extra_len,
+            });
+        }
+
+        let is_boneh = self.chain_spec.is_boneh(&number);
+        if number.0 % self.snapshot.epoch != 0 {
+            if !is_boneh && extra_len != EXTRA_VANITY_LEN + EXTRA_SEAL_LEN {
+                return Err(ParliaError::ExtraValidators { number });
+            }
+            return Ok(());
+        }
+
+        let (validators, _) = parse_epoch_validators(&header.extra_data, is_boneh)?;
+        if validators.is_empty() || validators.windows(2).any(|w| w[0] >= w[1]) {
+            return Err(ParliaError::UnsortedValidators { number });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{super::tests::*, *};
+
+    #[test]
+    fn verify_inturn_chain() {
+        let chain_spec = chain_spec(3, 10);
+        let keys = validator_keys(3);
+        let mut parent = genesis_header(keys.keys().copied());
+        let mut verifier = HeaderVerifier::new(&chain_spec, parent.clone()).unwrap();
+
+        for _ in 0..25 {
+            let snap = verifier.snapshot().clone();
+            let signer = snap.inturn_validator();
+            let header = next_header(&parent, &snap, &chain_spec, signer, &keys[&signer]);
+            verifier.verify(&header).unwrap();
+            parent = header;
+        }
+    }
+
+    #[test]
+    fn verify_rejects_invalid_headers() {
+        let chain_spec = chain_spec(3, 10);
+        let keys = validator_keys(3);
+        let genesis = genesis_header(keys.keys().copied());
+        let mut verifier = HeaderVerifier::new(&chain_spec, genesis.clone()).unwrap();
+        let snap = verifier.snapshot().clone();
+        let inturn = snap.inturn_validator();
+        let noturn = *keys.keys().find(|v| **v != inturn).unwrap();
+
+        let mut header = next_header(&genesis, &snap, &chain_spec, inturn, &keys[&inturn]);
+        header.difficulty = U256::one();
+        seal(&mut header, &chain_spec, &keys[&inturn]);
+        assert!(matches!(verifier.verify(&header), Err(ParliaError::WrongDifficulty { .. })));
+
+        let mut header = next_header(&genesis, &snap, &chain_spec, noturn, &keys[&noturn]);
+        header.timestamp -= 1;
+        seal(&mut header, &chain_spec, &keys[&noturn]);
+        assert!(matches!(verifier.verify(&header), Err(ParliaError::WrongHeaderTime { .. })));
+
+        let header = next_header(&genesis, &snap, &chain_spec, inturn, &keys[&noturn]);
+        assert!(matches!(verifier.verify(&header), Err(ParliaError::CoinbaseMismatch { .. })));
+
+        // the period holds before Ramanujan too, and a time past u64 is refused, not overflowed
+        let mut early = chain_spec.clone();
+        early.upgrades.ramanujan = None;
+        let mut verifier_early = HeaderVerifier::new(&early, genesis.clone()).unwrap();
+        let mut header = next_header(&genesis, &snap, &early, inturn, &keys[&inturn]);
+        header.timestamp = genesis.timestamp + 2;
+        seal(&mut header, &early, &keys[&inturn]);
+        assert!(matches!(verifier_early.verify(&header), Err(ParliaError::WrongHeaderTime { expected, .. }) if expected == genesis.timestamp + 3));
+        let mut parent = genesis.clone();
+        parent.timestamp = u64::MAX - 1;
+        let mut verifier_late = HeaderVerifier::new(&chain_spec, parent.clone()).unwrap();
+        let mut header = next_header(&parent, &snap, &chain_spec, inturn, &keys[&inturn]);
+        header.timestamp = u64::MAX;
+        seal(&mut header, &chain_spec, &keys[&inturn]);
+        assert_eq!(
+            Err(ParliaError::WrongHeaderTime { number: BlockNumber(1), expected: u64::MAX, got: u64::MAX }),
+            verifier_late.verify(&header)
+        );
+
+        // the in-turn validator may not seal again right away
+        let header = next_header(&genesis, &snap, &chain_spec, inturn, &keys[&inturn]);
+        verifier.verify(&header).unwrap();
+        let snap = verifier.snapshot().clone();
+        let again = next_header(&header, &snap, &chain_spec, inturn, &keys[&inturn]);
+        assert!(matches!(verifier.verify(&again), Err(ParliaError::SignerOverLimit { .. })));
+
+        // a zero epoch is refused instead of dividing by it
+        let mut chain_spec = chain_spec;
+        chain_spec.consensus.seal_verification = SealVerificationParams::Parlia { period: 3, epoch: 0 };
+        assert_eq!(Some(ParliaError::ZeroEpoch), HeaderVerifier::new(&chain_spec, genesis).err());
+    }
+
+    #[test]
+    fn verify_vote_attestation() {
+        use crate::models::vote::*;
+
+        let mut chain_spec = chain_spec(3, 10);
+        chain_spec.upgrades.boneh = Some(BlockNumber(0));
+        let keys = validator_keys(3);
+        let bls_secrets = bls_keys(3);
+        let mut genesis = genesis_header(None);
+        genesis.extra_data = boneh_extra_data(&keys, &bls_secrets);
+        let mut verifier = HeaderVerifier::new(&chain_spec, genesis.clone()).unwrap();
+        let snap = verifier.snapshot().clone();
+        let signer = snap.inturn_validator();
+
+        let data = VoteData {
+            source_number: 0,
+            source_hash: genesis.hash(),
+            target_number: 0,
+            target_hash: genesis.hash(),
+        };
+        let attestation = attest(&bls_secrets, 0b011, data);
+        let mut header = next_header(&genesis, &snap, &chain_spec, signer, &keys[&signer]);
+        set_attestation(&mut header, &chain_spec, &keys[&signer], &attestation);
+        assert_eq!(Some(attestation.clone()), parse_vote_attestation(&header, 10, true).unwrap());
+
+        let mut tampered = header.clone();
+        let wrong_voters = VoteAttestation { vote_address_set: 0b101, ..attestation };
+        set_attestation(&mut tampered, &chain_spec, &keys[&signer], &wrong_voters);
+        assert!(matches!(verifier.verify(&tampered), Err(ParliaError::WrongAttestationSignature { .. })));
+        verifier.verify(&header).unwrap();
+    }
+}
@@ -0,0 +1,1116 @@
+use crate::{
+    consensus::parlia::*,
+    models::{bsc, chainspec::*},
+    p2p::{parse_node_list, NodeRecord},
+    systemcontracts::{SystemContractUpgrade, UpgradeEffect},
+};
+use anyhow::bail;
+use clap::ArgEnum;
+use ethereum_types::{Address, H256, H512, H520, H64, U256};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+/// genesis_to_chain_spec converts a BSC geth `genesis.json` to an Akula chain spec.
+pub fn genesis_to_chain_spec(
+    genesis: &bsc::Genesis,
+    name: String,
+    p2p: P2PParams,
+) -> anyhow::Result<ChainSpec> {
+    let mut chain_spec = ChainSpec {
+        name,
+        consensus: ConsensusParams {
+            seal_verification: SealVerificationParams::Parlia {
+                period: genesis.config.parlia.period,
+                // geth takes its default epoch length for a zero one
+                epoch: match genesis.config.parlia.epoch {
+                    0 => DEFAULT_EPOCH_LENGTH,
+                    epoch => epoch,
+                },
+            },
+            eip1559_block: None
+        },
+        upgrades: Upgrades {
+            homestead: genesis.config.homestead_block,
+            tangerine: genesis.config.eip_150_block,
+            spurious: genesis.config.eip_155_block,
+            byzantium: genesis.config.byzantium_block,
+            constantinople: genesis.config.constantinople_block,
+            petersburg: genesis.config.petersburg_block,
+            istanbul: genesis.config.istanbul_block,
+            berlin: genesis.config.berlin_block,
+            london: genesis.config.london_block,
+            // not support in bsc
+            paris: None,
+            // bsc fork start
+            ramanujan: genesis.config.ramanujan_block,
+            niels: genesis.config.niels_block,
+            mirrorsync: genesis.config.mirror_sync_block,
+            bruno: genesis.config.bruno_block,
+            euler: genesis.config.euler_block,
+            gibbs: genesis.config.gibbs_block,
+            moran: genesis.config.moran_block,
+            planck: genesis.config.planck_block,
+            boneh: genesis.config.boneh_block,
+            lynn: genesis.config.lynn_block,
+            hertz: genesis.config.hertz_block
+        },
+        params: Params {
+            chain_id: ChainId(genesis.config.chain_id),
+            network_id: NetworkId(genesis.config.chain_id),
+            additional_forks: BTreeSet::new(),
+            eip1559: None,
+        },
+        genesis: Genesis {
+            number: BlockNumber(genesis.number),
+            parent_hash: genesis.parent_hash,
+            author: genesis.coinbase,
+            gas_limit: genesis.gas_limit,
+            gas_used: genesis.gas_used,
+            timestamp: genesis.timestamp,
+            seal: Seal::Unknown,
+            base_fee_per_gas: None
+        },
+        contracts: Default::default(),
+        balances: Default::default(),
+        p2p
+    };
+
+    // parse contracts and balances
+    let mut contracts = BTreeMap::new();
+    let mut balances = BTreeMap::new();
+    for (addr, account) in &genesis.alloc {
+        balances.insert(*addr, account.balance);
+        if let Some(code) = &account.code {
+            contracts.insert(*addr, Contract::Contract {
+                code: hex::decode(code.strip_prefix("0x").unwrap_or(&code))
+                    .map_err(|e| e)?
+                    .into()
+            });
+        }
+    }
+    chain_spec.contracts.insert(chain_spec.genesis.number, contracts);
+    chain_spec.balances.insert(chain_spec.genesis.number, balances);
+
+    // precompiles, later forks overriding earlier ones at the same block
+    for fork in bsc_precompiles(&chain_spec, &genesis.config) {
+        if let Some(block) = fork.block {
+            let contracts = chain_spec.contracts.entry(block.max(chain_spec.genesis.number)).or_default();
+            for (address, precompile) in fork.precompiles {
+                contracts.insert(address, Contract::Precompile(precompile));
+            }
+        }
+    }
+
+    // EIP-1559 comes with london, the base fee starts from genesis.json or BSC's default
+    if let Some(london) = chain_spec.upgrades.london {
+        let mut eip1559 = Eip1559Params::bsc();
+        if let Some(base_fee) = genesis.base_fee_per_gas {
+            eip1559.initial_base_fee = base_fee;
+        }
+        chain_spec.consensus.eip1559_block = Some(london);
+        chain_spec.params.eip1559 = Some(eip1559);
+    }
+    chain_spec.genesis.base_fee_per_gas = chain_spec
+        .eip1559_params(&chain_spec.genesis.number)
+        .map(|eip1559| eip1559.initial_base_fee);
+
+    // parse signers and bls keys
+    let (signers, bls_keys) = parse_epoch_validators(
+        &genesis.extra_data,
+        chain_spec.is_boneh(&chain_spec.genesis.number),
+    )?;
+    if let Some(bls_keys) = &bls_keys {
+        validate_bls_pub_keys(&signers, bls_keys)?;
+    }
+    let extra_data = &genesis.extra_data;
+    chain_spec.genesis.seal = Seal::Parlia {
+        vanity: H256::from_slice(&extra_data[..EXTRA_VANITY_LEN]),
+        score: match u64::try_from(genesis.difficulty) {
+            Ok(1) => BlockScore::NoTurn,
+            Ok(2) => BlockScore::InTurn,
+            _ => bail!("wrong parlia difficulty {}", genesis.difficulty),
+        },
+        signers,
+        bls_pub_keys: bls_keys,
+        mix_hash: genesis.mix_hash,
+        nonce: H64::from_low_u64_be(genesis.nonce),
+        seal: H520::from_slice(&extra_data[extra_data.len() - EXTRA_SEAL_LEN..]),
+    };
+
+    Ok(chain_spec)
+}
+
+/// Node list of a BSC geth `config.toml`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+pub enum NodeSource {
+    Static,
+    Trusted,
+    Bootstrap,
+    BootstrapV5,
+}
+
+impl NodeSource {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Static => "StaticNodes",
+            Self::Trusted => "TrustedNodes",
+            Self::Bootstrap => "BootstrapNodes",
+            Self::BootstrapV5 => "BootstrapNodesV5",
+        }
+    }
+
+    pub fn nodes<'a>(&self, config: &'a bsc::TomlConfig) -> &'a [String] {
+        let p2p = &config.node.p2p;
+        match self {
+            Self::Static => &p2p.static_nodes,
+            Self::Trusted => &p2p.trusted_nodes,
+            Self::Bootstrap => &p2p.bootstrap_nodes,
+            Self::BootstrapV5 => &p2p.bootstrap_nodes_v5,
+        }
+    }
+}
+
+/// Node lists that become bootnodes unless told otherwise, the static nodes as they always were
+pub const DEFAULT_BOOTNODE_SOURCES: &[NodeSource] = &[NodeSource::Static];
+
+/// Appends the nodes whose ID is not in `seen` yet.
+fn merge_nodes(out: &mut Vec<String>, seen: &mut BTreeSet<H512>, nodes: &[String], records: &[NodeRecord]) {
+    for (node, record) in nodes.iter().zip(records) {
+        if seen.insert(record.id()) {
+            out.push(node.clone());
+        }
+    }
+}
+
+/// p2p_params takes the P2P params of a chain spec from a BSC geth `config.toml`, `source`
+/// being its content. The node lists in `bootnode_sources` are merged, in that order, into
+/// the bootnodes, and the trusted nodes are kept as such, both without repeating a node ID.
+/// The DNS discovery trees of eth, then snap, peers go to `dns` and `additional_dns`.
+/// Every node list must hold valid enode URLs or ENRs, the invalid ones are all reported
+/// with the line they are on.
+pub fn p2p_params(
+    config: &bsc::TomlConfig,
+    source: &str,
+    bootnode_sources: &[NodeSource],
+) -> anyhow::Result<P2PParams> {
+    let mut records = BTreeMap::new();
+    let mut errors = Vec::new();
+    for node_source in [NodeSource::Static, NodeSource::Trusted, NodeSource::Bootstrap, NodeSource::BootstrapV5] {
+        match parse_node_list(node_source.nodes(config), Some(source)) {
+            Ok(list) => {
+                records.insert(node_source.name(), list);
+            }
+            Err(invalid) => {
+                errors.extend(invalid.iter().map(|node| format!("  {}: {}", node_source.name(), node)))
+            }
+        }
+    }
+    if !errors.is_empty() {
+        bail!("invalid nodes in config.toml:\n{}", errors.join("\n"));
+    }
+
+    let mut p2p = P2PParams::default();
+    let mut seen = BTreeSet::new();
+    for node_source in bootnode_sources {
+        merge_nodes(&mut p2p.bootnodes, &mut seen, node_source.nodes(config), &records[node_source.name()]);
+    }
+    let mut seen = BTreeSet::new();
+    let trusted = NodeSource::Trusted;
+    merge_nodes(&mut p2p.trusted_nodes, &mut seen, trusted.nodes(config), &records[trusted.name()]);
+
+    let mut dns = Vec::new();
+    for url in config.eth.eth_discovery_urls.iter().chain(&config.eth.snap_discovery_urls) {
+        if !dns.contains(url) {
+            dns.push(url.clone());
+        }
+    }
+    let mut dns = dns.into_iter();
+    p2p.dns = dns.next();
+    p2p.additional_dns = dns.collect();
+    Ok(p2p)
+}
+
+/// unknown_genesis_keys lists, as JSON paths, the keys of a genesis.json that `bsc::Genesis`
+/// does not know and drops on parsing, e.g. `config.lorentzTime` for a fork newer than the
+/// converter, in the top level, `config` and every `alloc` entry.
+pub fn unknown_genesis_keys(source: &str) -> anyhow::Result<Vec<String>> {
+    let value: serde_json::Value = serde_json::from_str(source)?;
+    let genesis: bsc::Genesis = serde_json::from_value(value.clone())?;
+    let mut known = serde_json::to_value(&genesis)?;
+    // alloc is keyed by address, every entry is checked against the keys of an account
+    let account = serde_json::to_value(bsc::GenesisAccount { balance: U256::zero(), code: None })?;
+    known["alloc"] = serde_json::Value::Null;
+
+    let mut unknown = Vec::new();
+    unknown_keys(&value, &known, "", &mut unknown);
+    if let Some(alloc) = value.get("alloc").and_then(|alloc| alloc.as_object()) {
+        for (address, entry) in alloc {
+            unknown_keys(entry, &account, &format!("alloc.{}", address), &mut unknown);
+        }
+    }
+    Ok(unknown)
+}
+
+/// Keys of the `value` objects missing in the `known` ones, `known` holding every key of
+/// the type `value` was parsed as.
+fn unknown_keys(value: &serde_json::Value, known: &serde_json::Value, path: &str, unknown: &mut Vec<String>) {
+    let (value, known) = match (value.as_object(), known.as_object()) {
+        (Some(value), Some(known)) => (value, known),
+        _ => return,
+    };
+    for (key, value) in value {
+        let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+        match known.get(key) {
+            Some(known) => unknown_keys(value, known, &path, unknown),
+            None => unknown.push(path),
+        }
+    }
+}
+
+/// PrecompileFork is a fork of BSC geth adding or repricing precompiles.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PrecompileFork {
+    pub fork: &'static str,
+    /// activation block, none if the fork is off or, for a timestamp fork, not yet on at genesis
+    pub block: Option<BlockNumber>,
+    /// genesis.json key and value of a timestamp fork
+    pub time: Option<(&'static str, u64)>,
+    pub precompiles: Vec<(Address, Precompile)>,
+}
+
+/// bsc_precompiles is the precompile table of BSC geth for the chain spec: the Ethereum
+/// precompiles, repriced at Istanbul and Berlin, and the BSC ones. The chain spec has no
+/// timestamp forks, so the precompiles of Feynman and Haber only come in when the fork is
+/// on at genesis.
+pub fn bsc_precompiles(chain_spec: &ChainSpec, config: &bsc::ChainConfig) -> Vec<PrecompileFork> {
+    let genesis = &chain_spec.genesis;
+    let u = &chain_spec.upgrades;
+    let fork = |fork, block, time, precompiles: Vec<(u64, Precompile)>| PrecompileFork {
+        fork,
+        block,
+        time,
+        precompiles: precompiles
+            .into_iter()
+            .map(|(address, precompile)| (Address::from_low_u64_be(address), precompile))
+            .collect(),
+    };
+    let timed = |fork_name, key, time: Option<u64>, precompiles| {
+        let block = time.filter(|time| *time <= genesis.timestamp).map(|_| genesis.number);
+        fork(fork_name, block, time.map(|time| (key, time)), precompiles)
+    };
+    vec![
+        fork("frontier", Some(genesis.number), None, vec![
+            (0x01, Precompile::EcRecover { base: 3000, word: 0 }),
+            (0x02, Precompile::Sha256 { base: 60, word: 12 }),
+            (0x03, Precompile::Ripemd160 { base: 600, word: 120 }),
+            (0x04, Precompile::Identity { base: 15, word: 3 }),
+        ]),
+        fork("byzantium", u.byzantium, None, vec![
+            (0x05, Precompile::ModExp { version: ModExpVersion::ModExp198 }),
+            (0x06, Precompile::AltBn128Add { price: 500 }),
+            (0x07, Precompile::AltBn128Mul { price: 40000 }),
+            (0x08, Precompile::AltBn128Pairing { base: 100000, pair: 80000 }),
+        ]),
+        fork("istanbul", u.istanbul, None, vec![
+            (0x06, Precompile::AltBn128Add { price: 150 }),
+            (0x07, Precompile::AltBn128Mul { price: 6000 }),
+            (0x08, Precompile::AltBn128Pairing { base: 45000, pair: 34000 }),
+            (0x09, Precompile::Blake2F { gas_per_round: 1 }),
+            (0x64, Precompile::TmHeaderValidate { price: 3000 }),
+            (0x65, Precompile::IavlMerkleProofValidate { price: 3000 }),
+        ]),
+        fork("berlin", u.berlin, None, vec![
+            (0x05, Precompile::ModExp { version: ModExpVersion::ModExp2565 }),
+        ]),
+        // fast finality, Luban in BSC geth
+        fork("boneh", u.boneh, None, vec![
+            (0x66, Precompile::BlsSignatureVerify { base: 1000, per_key: 3500 }),
+            (0x67, Precompile::CometBftLightBlockValidate { price: 3000 }),
+        ]),
+        timed("feynman", "feynmanTime", config.feynman_time, vec![
+            (0x68, Precompile::DoubleSignEvidenceValidate { price: 10000 }),
+            (0x69, Precompile::Secp256k1SignatureRecover { price: 3000 }),
+        ]),
+        timed("haber", "haberTime", config.haber_time, vec![
+            (0x100, Precompile::P256Verify { price: 3450 }),
+        ]),
+    ]
+}
+
+/// What a fork override of a config.toml did to the genesis chain config.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum OverrideEffect {
+    /// the override moved `fork`, scheduled at the block or time (`at`) `genesis` by genesis.json
+    Applied { fork: &'static str, at: &'static str, genesis: Option<u64> },
+    /// the chain spec has no such fork
+    Ignored,
+}
+
+/// ForkOverride is a fork override set in the `[Eth]` section of a config.toml.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForkOverride {
+    pub key: &'static str,
+    pub value: String,
+    pub effect: OverrideEffect,
+}
+
+impl std::fmt::Display for ForkOverride {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.effect {
+            OverrideEffect::Applied { fork, at, genesis: Some(genesis) } => {
+                write!(f, "{} at {} {} from Eth.{}, instead of {} in genesis.json", fork, at, self.value, self.key, genesis)
+            }
+            OverrideEffect::Applied { fork, at, genesis: None } => {
+                write!(f, "{} at {} {} from Eth.{}, unset in genesis.json", fork, at, self.value, self.key)
+            }
+            OverrideEffect::Ignored => write!(f, "Eth.{} = {} is ignored, the chain spec has no such fork", self.key, self.value),
+        }
+    }
+}
+
+/// apply_fork_overrides moves the forks of the genesis chain config as the `[Eth]` fork
+/// overrides of a node's config.toml say, as geth does on startup, and returns every
+/// override set.
+pub fn apply_fork_overrides(config: &mut bsc::ChainConfig, eth: &bsc::TomlEth) -> Vec<ForkOverride> {
+    let mut overrides = Vec::new();
+    if let Some(block) = eth.override_berlin {
+        overrides.push(ForkOverride {
+            key: "OverrideBerlin",
+            value: block.to_string(),
+            effect: OverrideEffect::Applied {
+                fork: "berlin",
+                at: "block",
+                genesis: config.berlin_block.replace(block).map(|block| block.0),
+            },
+        });
+    }
+    if let Some(time) = eth.override_feynman {
+        overrides.push(ForkOverride {
+            key: "OverrideFeynman",
+            value: time.to_string(),
+            effect: OverrideEffect::Applied { fork: "feynman", at: "time", genesis: config.feynman_time.replace(time) },
+        });
+    }
+    if let Some(time) = eth.override_haber {
+        overrides.push(ForkOverride {
+            key: "OverrideHaber",
+            value: time.to_string(),
+            effect: OverrideEffect::Applied { fork: "haber", at: "time", genesis: config.haber_time.replace(time) },
+        });
+    }
+    let ignored = [
+        ("OverrideArrowGlacier", eth.override_arrow_glacier.map(|block| block.to_string())),
+        ("OverrideTerminalTotalDifficulty", eth.override_terminal_total_difficulty.map(|ttd| ttd.to_string())),
+        (
+            "OverrideTerminalTotalDifficultyPassed",
+            eth.override_terminal_total_difficulty_passed.map(|passed| passed.to_string()),
+        ),
+        ("OverrideShanghai", eth.override_shanghai.map(|time| time.to_string())),
+        ("OverrideKepler", eth.override_kepler.map(|time| time.to_string())),
+        ("OverrideFeynmanFix", eth.override_feynman_fix.map(|time| time.to_string())),
+        ("OverrideCancun", eth.override_cancun.map(|time| time.to_string())),
+        ("OverrideBohr", eth.override_bohr.map(|time| time.to_string())),
+        ("OverridePascal", eth.override_pascal.map(|time| time.to_string())),
+        ("OverridePrague", eth.override_prague.map(|time| time.to_string())),
+        ("OverrideVerkle", eth.override_verkle.map(|time| time.to_string())),
+    ];
+    for (key, value) in ignored {
+        if let Some(value) = value {
+            overrides.push(ForkOverride { key, value, effect: OverrideEffect::Ignored });
+        }
+    }
+    overrides
+}
+
+/// apply_toml_config sets in the chain spec what the `[Eth]` section of a node's config.toml
+/// says of the chain, i.e. the network id the node runs the eth protocol on, and returns
+/// warnings about the config disagreeing with the genesis.
+pub fn apply_toml_config(chain_spec: &mut ChainSpec, config: &bsc::TomlConfig) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if let Some(network_id) = config.eth.network_id {
+        if network_id != chain_spec.params.chain_id.0 {
+            warnings.push(format!(
+                "Eth.NetworkId {} differs from the chain id {} of the genesis",
+                network_id, chain_spec.params.chain_id.0
+            ));
+        }
+        chain_spec.params.network_id = NetworkId(network_id);
+    }
+    warnings
+}
+
+/// How a field of the chain spec was set by the conversion.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FieldMapping {
+    /// path of the field in the chain spec, e.g. `upgrades.spurious`
+    pub field: String,
+    pub value: String,
+    /// input field the value comes from, e.g. `genesis.json config.eip155Block`
+    pub source: Option<String>,
+    /// default applied, with the reason for it, when no input sets the field
+    pub default: Option<String>,
+}
+
+/// Input field that does not reach the chain spec.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DroppedField {
+    pub field: String,
+    pub value: Option<String>,
+    pub reason: String,
+}
+
+/// ConversionReport explains every decision of a conversion: the source or default of each
+/// field of the chain spec, the input fields left out of it and the inputs that disagree.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct ConversionReport {
+    pub fields: Vec<FieldMapping>,
+    pub dropped: Vec<DroppedField>,
+    pub mismatches: Vec<String>,
+}
+
+fn show<T: std::fmt::Display>(value: Option<T>) -> String {
+    value.map_or_else(|| String::from("none"), |value| value.to_string())
+}
+
+impl ConversionReport {
+    fn mapped(&mut self, field: &str, value: String, source: &str) {
+        self.fields.push(FieldMapping { field: field.into(), value, source: Some(source.into()), default: None });
+    }
+
+    fn defaulted(&mut self, field: &str, value: String, default: &str) {
+        self.fields.push(FieldMapping { field: field.into(), value, source: None, default: Some(default.into()) });
+    }
+
+    fn dropped(&mut self, field: &str, value: Option<String>, reason: &str) {
+        self.dropped.push(DroppedField { field: field.into(), value, reason: reason.into() });
+    }
+
+    /// system_contract_upgrades adds the system contract upgrades of the `network` tables.
+    pub fn system_contract_upgrades(&mut self, network: &str, upgrades: &[SystemContractUpgrade]) {
+        for upgrade in upgrades {
+            let contracts = upgrade.contracts.join(", ");
+            match upgrade.effect {
+                UpgradeEffect::Scheduled(block) => self.mapped(
+                    &format!("system_contracts.{}", upgrade.fork),
+                    format!("{} at block {}", contracts, block),
+                    &format!("core/systemcontracts {}", network),
+                ),
+                UpgradeEffect::AtGenesis(_) => self.dropped(
+                    &format!("core/systemcontracts {} {}", network, upgrade.fork),
+                    Some(contracts),
+                    "on at genesis, the genesis.json alloc holds the code",
+                ),
+                UpgradeEffect::Off => self.dropped(
+                    &format!("core/systemcontracts {} {}", network, upgrade.fork),
+                    Some(contracts),
+                    "the fork is off",
+                ),
+            }
+        }
+    }
+}
+
+/// conversion_report describes the conversion of `genesis`, with the fork `overrides`
+/// already applied, and `config` into `chain_spec`, the bootnodes merged from
+/// `bootnode_sources`, dropping the `unknown_keys` of the genesis.json.
+pub fn conversion_report(
+    genesis: &bsc::Genesis,
+    config: &bsc::TomlConfig,
+    bootnode_sources: &[NodeSource],
+    overrides: &[ForkOverride],
+    unknown_keys: &[String],
+    chain_spec: &ChainSpec,
+) -> ConversionReport {
+    let mut report = ConversionReport::default();
+    let c = &genesis.config;
+    report.mapped("consensus.seal_verification.period", c.parlia.period.to_string(), "genesis.json config.parlia.period");
+    match c.parlia.epoch {
+        0 => report.defaulted(
+            "consensus.seal_verification.epoch",
+            DEFAULT_EPOCH_LENGTH.to_string(),
+            &format!("{}, geth's epoch length for a zero genesis.json config.parlia.epoch", DEFAULT_EPOCH_LENGTH),
+        ),
+        epoch => report.mapped("consensus.seal_verification.epoch", epoch.to_string(), "genesis.json config.parlia.epoch"),
+    }
+    match chain_spec.consensus.eip1559_block {
+        Some(block) => report.mapped("consensus.eip1559_block", block.to_string(), "genesis.json config.londonBlock"),
+        None => report.defaulted("consensus.eip1559_block", String::from("none"), "none, london is not scheduled"),
+    }
+    if let Some(eip1559) = &chain_spec.params.eip1559 {
+        let bsc = Eip1559Params::bsc();
+        match genesis.base_fee_per_gas {
+            Some(base_fee) => report.mapped("params.eip1559.initial_base_fee", base_fee.to_string(), "genesis.json baseFeePerGas"),
+            None => report.defaulted(
+                "params.eip1559.initial_base_fee",
+                eip1559.initial_base_fee.to_string(),
+                &format!("{}, BSC's initial base fee", bsc.initial_base_fee),
+            ),
+        }
+        report.defaulted(
+            "params.eip1559.elasticity_multiplier",
+            eip1559.elasticity_multiplier.to_string(),
+            &format!("{}, BSC's elasticity multiplier", bsc.elasticity_multiplier),
+        );
+        report.defaulted(
+            "params.eip1559.base_fee_change_denominator",
+            eip1559.base_fee_change_denominator.to_string(),
+            &format!("{}, BSC's base fee change denominator", bsc.base_fee_change_denominator),
+        );
+        report.defaulted(
+            "params.eip1559.base_fee_recipient",
+            format!("{:?}", eip1559.base_fee_recipient),
+            "SystemReward, BSC routes the base fee with the tip",
+        );
+    }
+
+    // the override that moved a fork, and the genesis.json value it replaced
+    let applied = |fork: &str| {
+        overrides.iter().find_map(|o| match o.effect {
+            OverrideEffect::Applied { fork: f, genesis, .. } if f == fork => Some((o.key, genesis)),
+            _ => None,
+        })
+    };
+
+    let u = &chain_spec.upgrades;
+    let forks = [
+        ("homestead", u.homestead, "homesteadBlock"),
+        ("tangerine", u.tangerine, "eip150Block"),
+        ("spurious", u.spurious, "eip155Block"),
+        ("byzantium", u.byzantium, "byzantiumBlock"),
+        ("constantinople", u.constantinople, "constantinopleBlock"),
+        ("petersburg", u.petersburg, "petersburgBlock"),
+        ("istanbul", u.istanbul, "istanbulBlock"),
+        ("berlin", u.berlin, "berlinBlock"),
+        ("london", u.london, "londonBlock"),
+        ("ramanujan", u.ramanujan, "ramanujanBlock"),
+        ("niels", u.niels, "nielsBlock"),
+        ("mirrorsync", u.mirrorsync, "mirrorSyncBlock"),
+        ("bruno", u.bruno, "brunoBlock"),
+        ("euler", u.euler, "eulerBlock"),
+        ("gibbs", u.gibbs, "gibbsBlock"),
+        ("moran", u.moran, "moranBlock"),
+        ("planck", u.planck, "planckBlock"),
+        ("boneh", u.boneh, "bonehBlock"),
+        ("lynn", u.lynn, "lynnBlock"),
+        ("hertz", u.hertz, "hertzBlock"),
+    ];
+    for (fork, block, key) in forks {
+        let field = format!("upgrades.{}", fork);
+        match applied(fork) {
+            Some((override_key, replaced)) => {
+                report.mapped(&field, show(block), &format!("config.toml Eth.{}", override_key));
+                report.dropped(
+                    &format!("genesis.json config.{}", key),
+                    replaced.map(|block| block.to_string()),
+                    &format!("replaced by Eth.{}", override_key),
+                );
+            }
+            None => report.mapped(&field, show(block), &format!("genesis.json config.{}", key)),
+        }
+    }
+    report.defaulted("upgrades.paris", show(u.paris), "none, BSC has no merge");
+    if let Some(block) = c.eip_158_block {
+        report.dropped("genesis.json config.eip158Block", Some(block.to_string()), "spurious comes from eip155Block");
+    }
+    if c.eip_155_block != c.eip_158_block {
+        report.mismatches.push(format!(
+            "genesis.json config.eip155Block {} differs from config.eip158Block {}, spurious is at {}",
+            show(c.eip_155_block),
+            show(c.eip_158_block),
+            show(u.spurious)
+        ));
+    }
+    if let Some(block) = c.muir_glacier_block {
+        report.dropped("genesis.json config.muirGlacierBlock", Some(block.to_string()), "the chain spec has no muir glacier");
+    }
+    report.dropped("genesis.json config.eip150Hash", Some(format!("{:?}", c.eip_150_hash)), "not kept in the chain spec");
+    for o in overrides.iter().filter(|o| o.effect == OverrideEffect::Ignored) {
+        report.dropped(&format!("config.toml Eth.{}", o.key), Some(o.value.clone()), "the chain spec has no such fork");
+    }
+
+    report.mapped("params.chain_id", chain_spec.params.chain_id.to_string(), "genesis.json config.chainId");
+    match config.eth.network_id {
+        Some(_) => report.mapped("params.network_id", chain_spec.params.network_id.to_string(), "config.toml Eth.NetworkId"),
+        None => report.defaulted("params.network_id", chain_spec.params.network_id.to_string(), "the chain id"),
+    }
+    if chain_spec.params.network_id.0 != chain_spec.params.chain_id.0 {
+        report.mismatches.push(format!(
+            "config.toml Eth.NetworkId {} differs from the chain id {}",
+            chain_spec.params.network_id, chain_spec.params.chain_id
+        ));
+    }
+
+    let g = &chain_spec.genesis;
+    report.mapped("genesis.number", g.number.to_string(), "genesis.json number");
+    report.mapped("genesis.parent_hash", format!("{:?}", g.parent_hash), "genesis.json parentHash");
+    report.mapped("genesis.author", format!("{:?}", g.author), "genesis.json coinbase");
+    report.mapped("genesis.gas_limit", g.gas_limit.to_string(), "genesis.json gasLimit");
+    report.mapped("genesis.gas_used", g.gas_used.to_string(), "genesis.json gasUsed");
+    report.mapped("genesis.timestamp", g.timestamp.to_string(), "genesis.json timestamp");
+    if let Seal::Parlia { vanity, score, signers, bls_pub_keys, mix_hash, nonce, seal } = &g.seal {
+        report.mapped("genesis.seal.vanity", format!("{:?}", vanity), "genesis.json extraData vanity");
+        report.mapped("genesis.seal.score", format!("{:?}", score), "genesis.json difficulty");
+        report.mapped("genesis.seal.signers", format!("{} validators", signers.len()), "genesis.json extraData");
+        report.mapped(
+            "genesis.seal.bls_pub_keys",
+            show(bls_pub_keys.as_ref().map(|keys| format!("{} keys", keys.len()))),
+            "genesis.json extraData",
+        );
+        report.mapped("genesis.seal.mix_hash", format!("{:?}", mix_hash), "genesis.json mixHash");
+        report.mapped("genesis.seal.nonce", format!("{:?}", nonce), "genesis.json nonce");
+        report.mapped("genesis.seal.seal", format!("{:?}", seal), "genesis.json extraData seal");
+    }
+    if let Ok(header) = rebuild_genesis_header(chain_spec, H256::zero()) {
+        if header.extra_data != genesis.extra_data {
+            report.mismatches.push(format!(
+                "genesis.json extraData 0x{} is not rebuilt as is from the chain spec, the genesis hash differs from geth's",
+                hex::encode(&genesis.extra_data)
+            ));
+        }
+    }
+    match (g.base_fee_per_gas, genesis.base_fee_per_gas) {
+        (Some(base_fee), Some(_)) => report.mapped("genesis.base_fee_per_gas", base_fee.to_string(), "genesis.json baseFeePerGas"),
+        (Some(base_fee), None) => report.defaulted("genesis.base_fee_per_gas", base_fee.to_string(), "BSC's initial base fee, london is on at genesis"),
+        (None, _) => report.defaulted("genesis.base_fee_per_gas", String::from("none"), "none, london is off at genesis"),
+    }
+
+    for fork in bsc_precompiles(chain_spec, c) {
+        let addresses = fork
+            .precompiles
+            .iter()
+            .map(|(address, _)| format!("{:#x}", address.to_low_u64_be()))
+            .collect::<Vec<_>>()
+            .join(", ");
+        let field = format!("precompiles.{}", fork.fork);
+        let source = |key| match applied(fork.fork) {
+            Some((override_key, _)) => format!("config.toml Eth.{}", override_key),
+            None => format!("genesis.json config.{}", key),
+        };
+        match (fork.block, fork.time) {
+            (Some(block), None) => {
+                report.defaulted(&field, format!("{} at block {}", addresses, block), "BSC geth's precompiles")
+            }
+            (Some(block), Some((key, _))) => report.mapped(&field, format!("{} at block {}", addresses, block), &source(key)),
+            (None, Some((key, time))) => report.dropped(
+                &source(key),
+                Some(time.to_string()),
+                &format!("after genesis and the chain spec has no timestamp forks, {} left out", addresses),
+            ),
+            (None, None) => {}
+        }
+        if let (Some((override_key, replaced)), Some((key, _))) = (applied(fork.fork), fork.time) {
+            report.dropped(
+                &format!("genesis.json config.{}", key),
+                replaced.map(|time| time.to_string()),
+                &format!("replaced by Eth.{}", override_key),
+            );
+        }
+    }
+    let contracts = genesis.alloc.values().filter(|account| account.code.is_some()).count();
+    report.mapped("contracts", format!("{} contracts", contracts), "genesis.json alloc code");
+    report.mapped("balances", format!("{} accounts", genesis.alloc.len()), "genesis.json alloc balance");
+    for key in unknown_keys {
+        report.dropped(&format!("genesis.json {}", key), None, "unknown key");
+    }
+
+    let p2p = &chain_spec.p2p;
+    let lists: Vec<_> = bootnode_sources.iter().map(|source| source.name()).collect();
+    report.mapped("p2p.bootnodes", format!("{} nodes", p2p.bootnodes.len()), &format!("config.toml {}", lists.join(", ")));
+    report.mapped("p2p.trusted_nodes", format!("{} nodes", p2p.trusted_nodes.len()), "config.toml TrustedNodes");
+    report.mapped("p2p.dns", show(p2p.dns.as_ref()), "config.toml Eth.EthDiscoveryURLs, Eth.SnapDiscoveryURLs");
+    report.mapped(
+        "p2p.additional_dns",
+        format!("{} trees", p2p.additional_dns.len()),
+        "config.toml Eth.EthDiscoveryURLs, Eth.SnapDiscoveryURLs",
+    );
+    report
+}
+
+impl std::fmt::Display for ConversionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "fields:")?;
+        for field in &self.fields {
+            write!(f, "  {} = {}", field.field, field.value)?;
+            if let Some(source) = &field.source {
+                write!(f, " <- {}", source)?;
+            }
+            if let Some(default) = &field.default {
+                write!(f, " (default: {})", default)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f, "dropped:")?;
+        for dropped in &self.dropped {
+            match &dropped.value {
+                Some(value) => writeln!(f, "  {} = {}: {}", dropped.field, value, dropped.reason)?,
+                None => writeln!(f, "  {}: {}", dropped.field, dropped.reason)?,
+            }
+        }
+        writeln!(f, "mismatches:")?;
+        for mismatch in &self.mismatches {
+            writeln!(f, "  {}", mismatch)?;
+        }
+        if self.mismatches.is_empty() {
+            writeln!(f, "  none")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::header::BlockHeader;
+
+    #[test]
+    fn merge_node_lists() {
+        let node = |key: u8, port: u16| {
+            let key = secp256k1::SecretKey::from_slice(&[key; 32]).unwrap();
+            crate::p2p::enode_url(&key, "127.0.0.1".parse().unwrap(), port)
+        };
+        let source = format!(
+            r#"[Eth]
+EthDiscoveryURLs = ["enrtree://A@bsc.example.org"]
+SnapDiscoveryURLs = ["enrtree://A@bsc.example.org", "enrtree://B@snap.example.org"]
+
+[Node]
+BLSPasswordFile = "password.txt"
+BLSWalletDir = "wallet"
+
+[Node.P2P]
+MaxPeers = 50
+NoDiscovery = false
+StaticNodes = ["{}", "{}"]
+TrustedNodes = ["{}", "{}"]
+BootstrapNodes = ["{}", "{}"]
+ListenAddr = ":30311"
+EnableMsgEvents = false
+"#,
+            node(1, 30311),
+            node(2, 30312),
+            node(3, 30313),
+            node(3, 30323),
+            // same node ID as a static node, on another port
+            node(2, 30322),
+            node(4, 30314),
+        );
+        let config: bsc::TomlConfig = toml::from_str(&source).unwrap();
+
+        let p2p = p2p_params(&config, &source, DEFAULT_BOOTNODE_SOURCES).unwrap();
+        assert_eq!(vec![node(1, 30311), node(2, 30312)], p2p.bootnodes);
+        assert_eq!(vec![node(3, 30313)], p2p.trusted_nodes);
+        assert_eq!(Some(String::from("enrtree://A@bsc.example.org")), p2p.dns);
+        assert_eq!(vec![String::from("enrtree://B@snap.example.org")], p2p.additional_dns);
+
+        let p2p = p2p_params(&config, &source, &[NodeSource::Bootstrap, NodeSource::Static]).unwrap();
+        assert_eq!(vec![node(2, 30322), node(4, 30314), node(1, 30311)], p2p.bootnodes);
+
+        let source = source.replace(&node(4, 30314), "enode://00@127.0.0.1:30314");
+        let config: bsc::TomlConfig = toml::from_str(&source).unwrap();
+        let err = p2p_params(&config, &source, DEFAULT_BOOTNODE_SOURCES).unwrap_err();
+        assert!(err.to_string().contains("BootstrapNodes: line 14:"));
+    }
+
+    fn test_genesis() -> bsc::Genesis {
+        serde_json::from_str(&format!(
+            r#"{{
+  "config": {{
+    "chainId": 714,
+    "eip150Hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+    "parlia": {{"period": 3, "epoch": 200}}
+  }},
+  "nonce": "0x0",
+  "timestamp": "0x0",
+  "extraData": "0x{}",
+  "gasLimit": "0x2625a00",
+  "difficulty": "0x1",
+  "mixHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+  "coinbase": "0xffffffffffffffffffffffffffffffffffffffff",
+  "alloc": {{}},
+  "number": "0x0",
+  "gasUsed": "0x0",
+  "parentHash": "0x0000000000000000000000000000000000000000000000000000000000000000"
+}}"#,
+            // vanity, one validator and the seal
+            "00".repeat(32 + 20 + 65)
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn network_id_from_config() {
+        let genesis = test_genesis();
+        let mut chain_spec = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap();
+
+        let mut config: bsc::TomlConfig = toml::from_str("[Eth]\nSyncMode = \"full\"\n").unwrap();
+        assert!(apply_toml_config(&mut chain_spec, &config).is_empty());
+        assert_eq!(NetworkId(714), chain_spec.params.network_id);
+
+        config.eth.network_id = Some(714);
+        assert!(apply_toml_config(&mut chain_spec, &config).is_empty());
+
+        config.eth.network_id = Some(1000);
+        let warnings = apply_toml_config(&mut chain_spec, &config);
+        assert_eq!(vec![String::from("Eth.NetworkId 1000 differs from the chain id 714 of the genesis")], warnings);
+        assert_eq!(NetworkId(1000), chain_spec.params.network_id);
+        assert_eq!(ChainId(714), chain_spec.params.chain_id);
+    }
+
+    #[test]
+    fn zero_epoch_takes_geth_default() {
+        let mut genesis = test_genesis();
+        genesis.config.parlia.epoch = 0;
+        let chain_spec = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap();
+        assert_eq!(Some(DEFAULT_EPOCH_LENGTH), chain_spec.consensus.parlia_epoch());
+
+        let report = conversion_report(&genesis, &Default::default(), &[], &[], &[], &chain_spec);
+        let epoch = report.fields.iter().find(|f| f.field == "consensus.seal_verification.epoch").unwrap();
+        assert_eq!("200", epoch.value);
+        assert_eq!(None, epoch.source);
+    }
+
+    #[test]
+    fn fork_overrides() {
+        let mut genesis = test_genesis();
+        genesis.config.berlin_block = Some(BlockNumber(31302048));
+        let config: bsc::TomlConfig =
+            toml::from_str("[Eth]\nOverrideBerlin = 100\nOverrideFeynman = 0\nOverrideHaber = 1718863500\nOverrideCancun = 1718863500\n")
+                .unwrap();
+        genesis.config.feynman_time = Some(1705996800);
+
+        let overrides = apply_fork_overrides(&mut genesis.config, &config.eth);
+        assert_eq!(
+            vec![
+                ForkOverride {
+                    key: "OverrideBerlin",
+                    value: "100".into(),
+                    effect: OverrideEffect::Applied { fork: "berlin", at: "block", genesis: Some(31302048) },
+                },
+                ForkOverride {
+                    key: "OverrideFeynman",
+                    value: "0".into(),
+                    effect: OverrideEffect::Applied { fork: "feynman", at: "time", genesis: Some(1705996800) },
+                },
+                ForkOverride {
+                    key: "OverrideHaber",
+                    value: "1718863500".into(),
+                    effect: OverrideEffect::Applied { fork: "haber", at: "time", genesis: None },
+                },
+                ForkOverride { key: "OverrideCancun", value: "1718863500".into(), effect: OverrideEffect::Ignored },
+            ],
+            overrides
+        );
+        assert_eq!("berlin at block 100 from Eth.OverrideBerlin, instead of 31302048 in genesis.json", overrides[0].to_string());
+        assert_eq!("haber at time 1718863500 from Eth.OverrideHaber, unset in genesis.json", overrides[2].to_string());
+        assert_eq!(Some(0), genesis.config.feynman_time);
+        assert_eq!(Some(1718863500), genesis.config.haber_time);
+
+        let chain_spec = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap();
+        assert_eq!(Some(BlockNumber(100)), chain_spec.upgrades.berlin);
+        // feynman is moved to genesis, so its precompiles come in there
+        assert!(chain_spec.contracts[&BlockNumber(0)].contains_key(&Address::from_low_u64_be(0x68)));
+
+        let report = conversion_report(&genesis, &config, &[], &overrides, &[], &chain_spec);
+        let field = |name: &str| report.fields.iter().find(|f| f.field == name).unwrap();
+        let dropped = |name: &str| report.dropped.iter().find(|d| d.field == name).unwrap();
+        assert_eq!(Some("config.toml Eth.OverrideFeynman"), field("precompiles.feynman").source.as_deref());
+        assert_eq!(Some("1705996800"), dropped("genesis.json config.feynmanTime").value.as_deref());
+        assert_eq!("replaced by Eth.OverrideFeynman", dropped("genesis.json config.feynmanTime").reason);
+        assert_eq!(Some("1718863500"), dropped("config.toml Eth.OverrideHaber").value.as_deref());
+        assert_eq!(None, dropped("genesis.json config.haberTime").value);
+
+        assert!(apply_fork_overrides(&mut genesis.config, &bsc::TomlEth::default()).is_empty());
+    }
+
+    #[test]
+    fn report_unknown_genesis_keys() {
+        let mut genesis = serde_json::to_value(test_genesis()).unwrap();
+        assert!(unknown_genesis_keys(&genesis.to_string()).unwrap().is_empty());
+
+        genesis["config"]["lorentzTime"] = 100.into();
+        genesis["config"]["parlia"]["blockInterval"] = 3.into();
+        genesis["excessBlobGas"] = "0x0".into();
+        genesis["alloc"] = serde_json::json!({
+            "27d92f736324E6d9F85d37a27a23AAAbe7162168": {"balance": "0x1", "nonce": "0x1"},
+            "0x0000000000000000000000000000000000001000": {"balance": "0x0", "code": "0x60", "storage": {}},
+        });
+        let mut unknown = unknown_genesis_keys(&genesis.to_string()).unwrap();
+        unknown.sort();
+        assert_eq!(
+            vec![
+                "alloc.0x0000000000000000000000000000000000001000.storage",
+                "alloc.27d92f736324E6d9F85d37a27a23AAAbe7162168.nonce",
+                "config.lorentzTime",
+                "config.parlia.blockInterval",
+                "excessBlobGas",
+            ],
+            unknown
+        );
+    }
+
+    #[test]
+    fn eip1559_params() {
+        let mut genesis = test_genesis();
+        let chain_spec = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap();
+        assert_eq!(None, chain_spec.consensus.eip1559_block);
+        assert_eq!(None, chain_spec.params.eip1559);
+        assert_eq!(None, chain_spec.genesis.base_fee_per_gas);
+
+        // BSC's zero base fee, from the london block on
+        genesis.config.london_block = Some(BlockNumber(100));
+        let chain_spec = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap();
+        assert_eq!(Some(BlockNumber(100)), chain_spec.consensus.eip1559_block);
+        assert_eq!(None, chain_spec.genesis.base_fee_per_gas);
+        assert_eq!(None, chain_spec.eip1559_params(&BlockNumber(99)));
+        assert_eq!(Some(&Eip1559Params::bsc()), chain_spec.eip1559_params(&BlockNumber(100)));
+
+        // the genesis base fee of genesis.json
+        genesis.config.london_block = Some(BlockNumber(0));
+        genesis.base_fee_per_gas = Some(U256::from(1_000_000_000));
+        let chain_spec = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap();
+        assert_eq!(Some(U256::from(1_000_000_000)), chain_spec.genesis.base_fee_per_gas);
+        let eip1559 = chain_spec.eip1559_params(&BlockNumber(0)).unwrap();
+        assert_eq!(U256::from(1_000_000_000), eip1559.initial_base_fee);
+        assert_eq!(BaseFeeRecipient::SystemReward, eip1559.base_fee_recipient);
+
+        let report = conversion_report(&genesis, &Default::default(), &[], &[], &[], &chain_spec);
+        let field = |name: &str| report.fields.iter().find(|f| f.field == name).unwrap();
+        assert_eq!(Some("genesis.json baseFeePerGas"), field("genesis.base_fee_per_gas").source.as_deref());
+        assert_eq!(Some("genesis.json config.londonBlock"), field("consensus.eip1559_block").source.as_deref());
+        assert_eq!("8", field("params.eip1559.base_fee_change_denominator").value);
+    }
+
+    #[test]
+    fn precompiles_by_fork() {
+        let mut genesis = test_genesis();
+        genesis.timestamp = 1000;
+        genesis.config.byzantium_block = Some(BlockNumber(0));
+        genesis.config.istanbul_block = Some(BlockNumber(0));
+        genesis.config.berlin_block = Some(BlockNumber(50));
+        genesis.config.boneh_block = Some(BlockNumber(100));
+        genesis.config.feynman_time = Some(1000);
+        genesis.config.haber_time = Some(2000);
+        let chain_spec = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap();
+        let precompile = |block: u64, address: u64| match &chain_spec.contracts[&BlockNumber(block)][&Address::from_low_u64_be(address)] {
+            Contract::Precompile(precompile) => precompile.clone(),
+            contract => panic!("unexpected contract {:?}", contract),
+        };
+
+        // istanbul prices win over byzantium ones at the same block
+        assert_eq!(Precompile::AltBn128Add { price: 150 }, precompile(0, 0x06));
+        assert_eq!(Precompile::ModExp { version: ModExpVersion::ModExp198 }, precompile(0, 0x05));
+        assert_eq!(Precompile::TmHeaderValidate { price: 3000 }, precompile(0, 0x64));
+        assert_eq!(Precompile::Secp256k1SignatureRecover { price: 3000 }, precompile(0, 0x69));
+        assert_eq!(Precompile::ModExp { version: ModExpVersion::ModExp2565 }, precompile(50, 0x05));
+        assert_eq!(Precompile::BlsSignatureVerify { base: 1000, per_key: 3500 }, precompile(100, 0x66));
+        assert_eq!(vec![BlockNumber(0), BlockNumber(50), BlockNumber(100)], chain_spec.contracts.keys().copied().collect::<Vec<_>>());
+
+        // haber comes after genesis, its precompile cannot be scheduled
+        let report = conversion_report(&genesis, &Default::default(), &[], &[], &[], &chain_spec);
+        let field = |name: &str| report.fields.iter().find(|f| f.field == name).unwrap();
+        assert_eq!("0x68, 0x69 at block 0", field("precompiles.feynman").value);
+        assert_eq!(Some("genesis.json config.feynmanTime"), field("precompiles.feynman").source.as_deref());
+        let haber = report.dropped.iter().find(|d| d.field == "genesis.json config.haberTime").unwrap();
+        assert_eq!("after genesis and the chain spec has no timestamp forks, 0x100 left out", haber.reason);
+    }
+
+    #[test]
+    fn explain_conversion() {
+        let mut genesis = test_genesis();
+        genesis.config.eip_155_block = Some(BlockNumber(0));
+        genesis.config.eip_158_block = Some(BlockNumber(10));
+        genesis.config.berlin_block = Some(BlockNumber(31302048));
+        let config: bsc::TomlConfig =
+            toml::from_str("[Eth]\nNetworkId = 97\nOverrideBerlin = 100\nOverrideCancun = 1718863500\n").unwrap();
+        let overrides = apply_fork_overrides(&mut genesis.config, &config.eth);
+        let mut chain_spec = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap();
+        apply_toml_config(&mut chain_spec, &config);
+
+        let unknown = vec![String::from("config.lorentzTime")];
+        let report = conversion_report(&genesis, &config, DEFAULT_BOOTNODE_SOURCES, &overrides, &unknown, &chain_spec);
+        let field = |name: &str| report.fields.iter().find(|f| f.field == name).unwrap();
+        let dropped = |name: &str| report.dropped.iter().find(|d| d.field == name).unwrap();
+
+        assert_eq!(Some("genesis.json config.eip155Block"), field("upgrades.spurious").source.as_deref());
+        assert_eq!("100", field("upgrades.berlin").value);
+        assert_eq!(Some("config.toml Eth.OverrideBerlin"), field("upgrades.berlin").source.as_deref());
+        assert_eq!(Some("31302048"), dropped("genesis.json config.berlinBlock").value.as_deref());
+        assert_eq!("spurious comes from eip155Block", dropped("genesis.json config.eip158Block").reason);
+        assert_eq!("the chain spec has no such fork", dropped("config.toml Eth.OverrideCancun").reason);
+        assert_eq!("unknown key", dropped("genesis.json config.lorentzTime").reason);
+        assert_eq!(None, field("upgrades.paris").source);
+        assert_eq!(Some("config.toml Eth.NetworkId"), field("params.network_id").source.as_deref());
+        assert_eq!(
+            vec![
+                String::from("genesis.json config.eip155Block 0 differs from config.eip158Block 10, spurious is at 0"),
+                String::from("config.toml Eth.NetworkId 97 differs from the chain id 714"),
+            ],
+            report.mismatches
+        );
+
+        let text = report.to_string();
+        assert!(text.contains("  upgrades.berlin = 100 <- config.toml Eth.OverrideBerlin\n"));
+        assert!(text.contains("  upgrades.paris = none (default: none, BSC has no merge)\n"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!("upgrades.berlin", json["fields"][10]["field"]);
+    }
+
+    #[test]
+    fn lossless_genesis_header() {
+        let mut genesis = test_genesis();
+        let mut extra_data = genesis.extra_data.to_vec();
+        extra_data[..32].copy_from_slice(&[0xab; 32]);
+        let seal_start = extra_data.len() - EXTRA_SEAL_LEN;
+        extra_data[seal_start..].copy_from_slice(&[0xcd; EXTRA_SEAL_LEN]);
+        genesis.extra_data = extra_data.into();
+        genesis.parent_hash = H256::repeat_byte(1);
+        genesis.mix_hash = H256::repeat_byte(2);
+        genesis.nonce = 0x42;
+        genesis.gas_used = 21000;
+        genesis.difficulty = U256::from(2);
+        genesis.config.london_block = Some(BlockNumber(0));
+        let chain_spec = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap();
+
+        // the spec survives its RON form
+        let ron = ron::ser::to_string_pretty(&chain_spec, ron::ser::PrettyConfig::new()).unwrap();
+        let chain_spec: ChainSpec = ron::from_str(&ron).unwrap();
+
+        // the header geth builds from genesis.json
+        let state_root = H256::repeat_byte(3);
+        let geth = BlockHeader {
+            parent_hash: genesis.parent_hash,
+            ommers_hash: EMPTY_LIST_HASH,
+            beneficiary: genesis.coinbase,
+            state_root,
+            transactions_root: EMPTY_ROOT_HASH,
+            receipts_root: EMPTY_ROOT_HASH,
+            logs_bloom: Default::default(),
+            difficulty: genesis.difficulty,
+            number: BlockNumber(0),
+            gas_limit: genesis.gas_limit,
+            gas_used: 21000,
+            timestamp: genesis.timestamp,
+            extra_data: genesis.extra_data.clone(),
+            mix_hash: genesis.mix_hash,
+            nonce: H64::from_low_u64_be(0x42),
+            base_fee_per_gas: Some(U256::zero()),
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            requests_hash: None,
+        };
+        let header = rebuild_genesis_header(&chain_spec, state_root).unwrap();
+        assert_eq!(geth, header);
+        assert_eq!(geth.hash(), header.hash());
+
+        let report = conversion_report(&genesis, &Default::default(), &[], &[], &[], &chain_spec);
+        assert!(report.mismatches.is_empty());
+        assert!(report.dropped.iter().all(|d| !d.field.starts_with("genesis.json extraData")));
+
+        // a difficulty above 64 bits is an error, not a panic
+        genesis.difficulty = U256::one() << 64;
+        let err = genesis_to_chain_spec(&genesis, "BSC-devnet".into(), P2PParams::default()).unwrap_err();
+        assert_eq!("wrong parlia difficulty 18446744073709551616", err.to_string());
+    }
+}
@@ -0,0 +1,102 @@
+use crate::models::bls::*;
+use blst::{min_pk, BLST_ERROR};
+use ethereum_types::{Address, H256};
+use secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId},
+    Message, PublicKey, SecretKey, SECP256K1,
+};
+use sha3::{Digest, Keccak256};
+
+/// keccak256 hash of the input bytes
+pub fn keccak256(data: impl AsRef<[u8]>) -> H256 {
+    H256::from_slice(&Keccak256::digest(data.as_ref()))
+}
+
+/// Ethereum address of a secp256k1 public key
+pub fn public_to_address(public: &PublicKey) -> Address {
+    Address::from_slice(&keccak256(&public.serialize_uncompressed()[1..])[12..])
+}
+
+/// Recovers the signer's public key from a 65 bytes `[r || s || v]` signature over a 32 bytes hash.
+pub fn recover_public(sig: &[u8], hash: H256) -> Result<PublicKey, secp256k1::Error> {
+    if sig.len() != 65 {
+        return Err(secp256k1::Error::InvalidSignature);
+    }
+    let rec_id = RecoveryId::from_i32(sig[64] as i32)?;
+    let sig = RecoverableSignature::from_compact(&sig[..64], rec_id)?;
+    SECP256K1.recover_ecdsa(&Message::from_slice(hash.as_bytes())?, &sig)
+}
+
+/// Signs a 32 bytes hash, returning the 65 bytes `[r || s || v]` signature.
+pub fn sign(hash: H256, secret: &SecretKey) -> Result<[u8; 65], secp256k1::Error> {
+    let (rec_id, data) = SECP256K1
+        .sign_ecdsa_recoverable(&Message::from_slice(hash.as_bytes())?, secret)
+        .serialize_compact();
+    let mut sig = [0u8; 65];
+    sig[..64].copy_from_slice(&data);
+    sig[64] = rec_id.to_i32() as u8;
+    Ok(sig)
+}
+
+/// Domain separation tag of the BLS signatures of fast finality votes, the proof of possession scheme
+pub const BLS_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+
+fn bls_result(err: BLST_ERROR) -> Result<(), BLST_ERROR> {
+    match err {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        err => Err(err),
+    }
+}
+
+fn bls_public_key(public: &BLSPublicKey) -> Result<min_pk::PublicKey, BLST_ERROR> {
+    min_pk::PublicKey::key_validate(public.as_bytes())
+}
+
+/// Checks that a compressed BLS public key decodes to a BLS12-381 G1 point on the curve,
+/// in the prime order subgroup and not the point at infinity.
+pub fn bls_validate_public(public: &BLSPublicKey) -> Result<(), BLST_ERROR> {
+    bls_public_key(public).map(|_| ())
+}
+
+/// BLS public key of a secret key, compressed
+pub fn bls_public(secret: &min_pk::SecretKey) -> BLSPublicKey {
+    BLSPublicKey(secret.sk_to_pk().to_bytes())
+}
+
+/// Signs a message with a BLS secret key, returning the compressed signature.
+pub fn bls_sign(secret: &min_pk::SecretKey, msg: &[u8]) -> BLSSignature {
+    BLSSignature(secret.sign(msg, BLS_DST, &[]).to_bytes())
+}
+
+/// Aggregates BLS signatures, of the same message or not.
+pub fn bls_aggregate(sigs: &[BLSSignature]) -> Result<BLSSignature, BLST_ERROR> {
+    let sigs = sigs
+        .iter()
+        .map(|sig| min_pk::Signature::sig_validate(sig.as_bytes(), true))
+        .collect::<Result<Vec<_>, _>>()?;
+    let sigs: Vec<&min_pk::Signature> = sigs.iter().collect();
+    let agg = min_pk::AggregateSignature::aggregate(&sigs, false)?;
+    Ok(BLSSignature(agg.to_signature().to_bytes()))
+}
+
+/// Verifies the BLS signature of a message.
+pub fn bls_verify(public: &BLSPublicKey, msg: &[u8], sig: &BLSSignature) -> Result<(), BLST_ERROR> {
+    let public = bls_public_key(public)?;
+    let sig = min_pk::Signature::from_bytes(sig.as_bytes())?;
+    bls_result(sig.verify(true, msg, BLS_DST, &[], &public, false))
+}
+
+/// Verifies a BLS signature aggregated from the signatures of the same message by all the public keys.
+pub fn bls_fast_aggregate_verify(
+    publics: &[BLSPublicKey],
+    msg: &[u8],
+    sig: &BLSSignature,
+) -> Result<(), BLST_ERROR> {
+    if publics.is_empty() {
+        return Err(BLST_ERROR::BLST_VERIFY_FAIL);
+    }
+    let publics = publics.iter().map(bls_public_key).collect::<Result<Vec<_>, _>>()?;
+    let publics: Vec<&min_pk::PublicKey> = publics.iter().collect();
+    let sig = min_pk::Signature::from_bytes(sig.as_bytes())?;
+    bls_result(sig.fast_aggregate_verify(true, msg, BLS_DST, &publics))
+}
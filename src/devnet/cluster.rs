@@ -0,0 +1,88 @@
+use super::BLS_WALLET_DIR;
+use crate::{crypto::*, models::bsc::*, p2p::enode_url};
+use anyhow::bail;
+use secp256k1::SecretKey;
+use std::{net::IpAddr, path::Path};
+
+/// Node of a local cluster: the key its node ID derives from and the port it listens on.
+pub struct ClusterNode {
+    pub index: u32,
+    pub node_key: SecretKey,
+    pub port: u16,
+}
+
+impl ClusterNode {
+    pub fn enode(&self, ip: IpAddr) -> String {
+        enode_url(&self.node_key, ip, self.port)
+    }
+}
+
+/// Node key of the cluster node `index`, derived from the cluster seed.
+pub fn node_key(seed: &[u8], index: u32) -> Result<SecretKey, secp256k1::Error> {
+    SecretKey::from_slice(keccak256([seed, b"nodekey", &index.to_be_bytes()].concat()).as_bytes())
+}
+
+/// cluster_nodes derives `count` nodes from the seed, listening on consecutive ports from
+/// `base_port`.
+pub fn cluster_nodes(seed: &[u8], count: u32, base_port: u16) -> anyhow::Result<Vec<ClusterNode>> {
+    if count == 0 {
+        bail!("a cluster needs at least one node");
+    }
+    (0..count)
+        .map(|index| {
+            let port = match u16::try_from(index).ok().and_then(|i| base_port.checked_add(i)) {
+                Some(port) => port,
+                None => bail!("no port left for node {} from {}", index, base_port),
+            };
+            Ok(ClusterNode { index, node_key: node_key(seed, index)?, port })
+        })
+        .collect()
+}
+
+/// node_toml_config lays out the geth `config.toml` of a cluster node: it listens on its own
+/// port, dials every other node and votes with the BLS wallet in its dir.
+pub fn node_toml_config(nodes: &[ClusterNode], node: &ClusterNode, ip: IpAddr, dir: &Path) -> TomlConfig {
+    TomlConfig {
+        eth: TomlEth::default(),
+        node: TomlNode {
+            bls_password_file: Some(dir.join("bls").join("password.txt").to_string_lossy().into_owned()),
+            bls_wallet_dir: Some(dir.join(BLS_WALLET_DIR).to_string_lossy().into_owned()),
+            p2p: TomlP2P {
+                // the cluster is meshed by its static nodes
+                no_discovery: true,
+                static_nodes: nodes.iter().filter(|n| n.index != node.index).map(|n| n.enode(ip)).collect(),
+                listen_addr: format!(":{}", node.port),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        metrics: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn cluster_configs() {
+        let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let nodes = cluster_nodes(&[7; 32], 3, 30311).unwrap();
+        assert_eq!(vec![30311, 30312, 30313], nodes.iter().map(|n| n.port).collect::<Vec<_>>());
+        assert_eq!(nodes[1].enode(ip), cluster_nodes(&[7; 32], 2, 30311).unwrap()[1].enode(ip));
+        assert_ne!(nodes[0].node_key, nodes[1].node_key);
+
+        let enode = nodes[0].enode(ip);
+        assert!(enode.starts_with("enode://") && enode.ends_with("@127.0.0.1:30311"));
+        assert_eq!(128, enode["enode://".len()..enode.find('@').unwrap()].len());
+
+        let config = node_toml_config(&nodes, &nodes[1], ip, Path::new("/cluster/node1"));
+        let config: TomlConfig = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(":30312", config.node.p2p.listen_addr);
+        assert_eq!(vec![nodes[0].enode(ip), nodes[2].enode(ip)], config.node.p2p.static_nodes);
+        assert_eq!(Some("/cluster/node1/bls/wallet"), config.node.bls_wallet_dir.as_deref());
+
+        assert!(cluster_nodes(&[7; 32], 2, u16::MAX).is_err());
+    }
+}
@@ -0,0 +1,230 @@
+use crate::{
+    consensus::parlia::*,
+    models::{bls::BLSPublicKey, bsc, chainspec::*},
+};
+use anyhow::bail;
+use bytes::Bytes;
+use ethereum_types::{Address, H256, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Validator of a devnet genesis, its BLS vote key is needed if Boneh is active at genesis.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GenesisValidator {
+    pub address: Address,
+    #[serde(
+    default,
+    skip_serializing_if = "Option::is_none",
+    with = "::serde_with::rust::unwrap_or_skip"
+    )]
+    pub bls_pub_key: Option<BLSPublicKey>,
+}
+
+fn default_gas_limit() -> u64 {
+    40_000_000
+}
+
+/// Devnet genesis to generate, as read from a .ron file.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DevnetConfig {
+    pub chain_id: u64,
+    pub period: u64,
+    pub epoch: u64,
+    #[serde(default)]
+    pub timestamp: u64,
+    #[serde(default = "default_gas_limit")]
+    pub gas_limit: u64,
+    pub upgrades: Upgrades,
+    #[serde(default)]
+    pub validators: Vec<GenesisValidator>,
+    /// prefunded accounts
+    #[serde(default)]
+    pub balances: BTreeMap<Address, U256>,
+    /// files holding the hex runtime bytecode of system contracts, by address
+    #[serde(default)]
+    pub contracts: BTreeMap<Address, String>,
+    /// chain spec to take the genesis contracts missing from `contracts` from
+    #[serde(
+    default,
+    skip_serializing_if = "Option::is_none",
+    with = "::serde_with::rust::unwrap_or_skip"
+    )]
+    pub contracts_from: Option<String>,
+}
+
+/// build_genesis lays out the geth `genesis.json` of a devnet: its fork schedule, its
+/// validators in extraData, in the Boneh layout if Boneh is active at genesis, and its
+/// accounts with the given system contract bytecode.
+pub fn build_genesis(config: &DevnetConfig, contracts: &BTreeMap<Address, Bytes>) -> anyhow::Result<bsc::Genesis> {
+    let mut validators = config.validators.clone();
+    validators.sort_by_key(|v| v.address);
+    if validators.is_empty() {
+        bail!("a parlia genesis needs one validator at least");
+    }
+    if config.epoch == 0 {
+        bail!("the epoch length must not be zero");
+    }
+    if let Some(w) = validators.windows(2).find(|w| w[0].address == w[1].address) {
+        bail!("validator {:?} is listed twice", w[0].address);
+    }
+
+    let addresses: Vec<Address> = validators.iter().map(|v| v.address).collect();
+    let extra_data = if is_forked(config.upgrades.boneh, &BlockNumber(0)) {
+        if addresses.len() > u8::MAX as usize {
+            bail!("{} validators, the Boneh extraData holds {} at most", addresses.len(), u8::MAX);
+        }
+        let mut bls_pub_keys = Vec::new();
+        for validator in &validators {
+            match validator.bls_pub_key {
+                Some(key) => bls_pub_keys.push(key),
+                None => bail!("validator {:?} has no BLS key, it needs one since Boneh is active at genesis", validator.address),
+            }
+        }
+        validate_bls_pub_keys(&addresses, &bls_pub_keys)?;
+        encode_epoch_extra_data(H256::zero(), &addresses, Some(&bls_pub_keys))?
+    } else {
+        encode_epoch_extra_data(H256::zero(), &addresses, None)?
+    };
+
+    let upgrades = &config.upgrades;
+    let chain_config = bsc::ChainConfig {
+        chain_id: config.chain_id,
+        homestead_block: upgrades.homestead,
+        eip_150_block: upgrades.tangerine,
+        eip_150_hash: H256::zero(),
+        eip_155_block: upgrades.spurious,
+        eip_158_block: upgrades.spurious,
+        byzantium_block: upgrades.byzantium,
+        constantinople_block: upgrades.constantinople,
+        petersburg_block: upgrades.petersburg,
+        istanbul_block: upgrades.istanbul,
+        muir_glacier_block: upgrades.istanbul,
+        ramanujan_block: upgrades.ramanujan,
+        niels_block: upgrades.niels,
+        euler_block: upgrades.euler,
+        bruno_block: upgrades.bruno,
+        mirror_sync_block: upgrades.mirrorsync,
+        berlin_block: upgrades.berlin,
+        london_block: upgrades.london,
+        gibbs_block: upgrades.gibbs,
+        boneh_block: upgrades.boneh,
+        moran_block: upgrades.moran,
+        planck_block: upgrades.planck,
+        lynn_block: upgrades.lynn,
+        hertz_block: upgrades.hertz,
+        feynman_time: None,
+        haber_time: None,
+        parlia: bsc::ParliaConfig {
+            period: config.period,
+            epoch: config.epoch,
+        },
+    };
+
+    let mut alloc = BTreeMap::new();
+    for (address, balance) in &config.balances {
+        alloc.insert(*address, bsc::GenesisAccount { balance: *balance, code: None });
+    }
+    for (address, code) in contracts {
+        let account = alloc
+            .entry(*address)
+            .or_insert_with(|| bsc::GenesisAccount { balance: U256::zero(), code: None });
+        account.code = Some(format!("0x{}", hex::encode(code)));
+    }
+
+    Ok(bsc::Genesis {
+        config: chain_config,
+        number: 0,
+        parent_hash: H256::zero(),
+        nonce: 0,
+        timestamp: config.timestamp,
+        extra_data: extra_data.into(),
+        gas_limit: config.gas_limit,
+        gas_used: 0,
+        difficulty: U256::from(BlockScore::NoTurn as u64),
+        mix_hash: H256::zero(),
+        coinbase: Address::zero(),
+        alloc,
+        base_fee_per_gas: None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{convert::genesis_to_chain_spec, crypto::bls_public};
+
+    fn devnet_config(boneh: Option<BlockNumber>) -> DevnetConfig {
+        let validators = (1..=3u8)
+            .map(|i| GenesisValidator {
+                address: Address::repeat_byte(4 - i),
+                bls_pub_key: Some(bls_public(&blst::min_pk::SecretKey::key_gen(&[i; 32], &[]).unwrap())),
+            })
+            .collect();
+        DevnetConfig {
+            chain_id: 714,
+            period: 3,
+            epoch: 200,
+            timestamp: 0,
+            gas_limit: default_gas_limit(),
+            upgrades: Upgrades { ramanujan: Some(BlockNumber(0)), boneh, ..Default::default() },
+            validators,
+            balances: [(Address::repeat_byte(0xaa), U256::exp10(24))].into_iter().collect(),
+            contracts: Default::default(),
+            contracts_from: None,
+        }
+    }
+
+    #[test]
+    fn build_boneh_genesis() {
+        let config = devnet_config(Some(BlockNumber(0)));
+        let contracts = [(Address::from_low_u64_be(0x1000), Bytes::from_static(&[0x60, 0x80]))].into_iter().collect();
+        let genesis = build_genesis(&config, &contracts).unwrap();
+        let genesis: bsc::Genesis = serde_json::from_str(&serde_json::to_string(&genesis).unwrap()).unwrap();
+        let chain_spec = genesis_to_chain_spec(&genesis, String::from("devnet"), P2PParams::default()).unwrap();
+
+        match chain_spec.genesis.seal {
+            Seal::Parlia { signers, bls_pub_keys: Some(bls_pub_keys), .. } => {
+                assert_eq!((1..=3u8).map(Address::repeat_byte).collect::<Vec<_>>(), signers);
+                assert_eq!(config.validators[2].bls_pub_key, Some(bls_pub_keys[0]));
+            }
+            seal => panic!("unexpected seal {:?}", seal),
+        }
+        assert_eq!(U256::exp10(24), chain_spec.balances[&BlockNumber(0)][&Address::repeat_byte(0xaa)]);
+        let contracts = &chain_spec.contracts[&BlockNumber(0)];
+        assert_eq!(1, contracts.values().filter(|contract| matches!(contract, Contract::Contract { .. })).count());
+        assert!(contracts.contains_key(&Address::from_low_u64_be(0x1000)));
+    }
+
+    #[test]
+    fn reject_missing_bls_keys() {
+        let mut config = devnet_config(Some(BlockNumber(0)));
+        config.validators[1].bls_pub_key = None;
+        assert!(build_genesis(&config, &BTreeMap::new()).is_err());
+
+        // before Boneh extraData lists addresses only
+        config.upgrades.boneh = Some(BlockNumber(100));
+        let genesis = build_genesis(&config, &BTreeMap::new()).unwrap();
+        assert_eq!(EXTRA_VANITY_LEN + 3 * EXTRA_VALIDATOR_LEN + EXTRA_SEAL_LEN, genesis.extra_data.len());
+    }
+
+    #[test]
+    fn reject_invalid_devnet_config() {
+        let mut config = devnet_config(Some(BlockNumber(0)));
+        config.epoch = 0;
+        let err = build_genesis(&config, &BTreeMap::new()).unwrap_err();
+        assert_eq!("the epoch length must not be zero", err.to_string());
+
+        // the Boneh validator count is a single byte
+        let mut config = devnet_config(Some(BlockNumber(0)));
+        let validator = config.validators[0].clone();
+        config.validators = (0..256u64)
+            .map(|i| GenesisValidator { address: Address::from_low_u64_be(i + 1), ..validator.clone() })
+            .collect();
+        let err = build_genesis(&config, &BTreeMap::new()).unwrap_err();
+        assert_eq!("256 validators, the Boneh extraData holds 255 at most", err.to_string());
+        assert_eq!(
+            Err(ParliaError::TooManyValidators { count: 256 }),
+            encode_epoch_extra_data(H256::zero(), &vec![Address::zero(); 256], Some(&[]))
+        );
+    }
+}
@@ -0,0 +1,191 @@
+use crate::{crypto::*, keystore::*, models::bls::BLSPublicKey};
+use ethereum_types::Address;
+use hmac::{Hmac, Mac};
+use secp256k1::{PublicKey, SecretKey, SECP256K1};
+use serde::{Deserialize, Serialize};
+use sha2::Sha512;
+use std::{fs, path::Path};
+use unicode_normalization::UnicodeNormalization;
+
+/// prysm wallet of a validator's BLS vote key, relative to its dir, for geth's BLSWalletDir
+pub const BLS_WALLET_DIR: &str = "bls/wallet";
+
+/// First index of BIP-32 hardened children
+const HARDENED: u32 = 0x8000_0000;
+
+/// Validator as listed in a Boneh genesis: its address and BLS vote key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorInfo {
+    pub address: Address,
+    pub bls_pub_key: BLSPublicKey,
+}
+
+/// Keys of a devnet validator: the account it seals with and the BLS key it votes with.
+pub struct ValidatorKey {
+    pub index: u32,
+    pub secret: SecretKey,
+    pub bls_secret: blst::min_pk::SecretKey,
+}
+
+impl ValidatorKey {
+    pub fn address(&self) -> Address {
+        public_to_address(&PublicKey::from_secret_key(SECP256K1, &self.secret))
+    }
+
+    pub fn info(&self) -> ValidatorInfo {
+        ValidatorInfo {
+            address: self.address(),
+            bls_pub_key: bls_public(&self.bls_secret),
+        }
+    }
+
+    /// BIP-44 path of the validator account, the first accounts of wallets like MetaMask
+    pub fn path(&self) -> String {
+        format!("m/44'/60'/0'/0/{}", self.index)
+    }
+
+    /// EIP-2334 path of the validator signing key
+    pub fn bls_path(&self) -> String {
+        format!("m/12381/3600/{}/0/0", self.index)
+    }
+}
+
+/// BIP-39 seed of a mnemonic. The words are not checked against a word list, so any
+/// phrase works as a devnet seed.
+pub fn mnemonic_to_seed(mnemonic: &str, passphrase: &str) -> [u8; 64] {
+    let mnemonic: String = mnemonic.nfkd().collect();
+    let salt: String = format!("mnemonic{}", passphrase).nfkd().collect();
+    let mut seed = [0u8; 64];
+    pbkdf2::pbkdf2::<Hmac<Sha512>>(mnemonic.as_bytes(), salt.as_bytes(), 2048, &mut seed);
+    seed
+}
+
+fn hmac_sha512(key: &[u8], data: &[&[u8]]) -> [u8; 64] {
+    let mut mac = Hmac::<Sha512>::new_from_slice(key).expect("hmac takes keys of any length");
+    for d in data {
+        mac.update(d);
+    }
+    mac.finalize().into_bytes().into()
+}
+
+/// BIP-32 derivation of a secp256k1 secret key from a seed.
+fn bip32_derive(seed: &[u8], path: &[u32]) -> Result<SecretKey, secp256k1::Error> {
+    let i = hmac_sha512(b"Bitcoin seed", &[seed]);
+    let mut secret = SecretKey::from_slice(&i[..32])?;
+    let mut chain_code = i[32..].to_vec();
+    for index in path {
+        let i = if *index >= HARDENED {
+            hmac_sha512(&chain_code, &[&[0], &secret.secret_bytes(), &index.to_be_bytes()])
+        } else {
+            let public = PublicKey::from_secret_key(SECP256K1, &secret).serialize();
+            hmac_sha512(&chain_code, &[&public, &index.to_be_bytes()])
+        };
+        secret.add_assign(&i[..32])?;
+        chain_code = i[32..].to_vec();
+    }
+    Ok(secret)
+}
+
+/// EIP-2333 derivation of a BLS secret key from a seed of 32 bytes at least.
+fn eip2333_derive(seed: &[u8], path: &[u32]) -> blst::min_pk::SecretKey {
+    let mut secret = blst::min_pk::SecretKey::derive_master_eip2333(seed).expect("seed of 32 bytes at least");
+    for index in path {
+        secret = secret.derive_child_eip2333(*index);
+    }
+    secret
+}
+
+/// derive_validator_keys derives the keys of `count` validators from a seed of 32 bytes at
+/// least: the account at m/44'/60'/0'/0/i and the BLS signing key at m/12381/3600/i/0/0.
+pub fn derive_validator_keys(seed: &[u8], count: u32) -> Result<Vec<ValidatorKey>, secp256k1::Error> {
+    (0..count)
+        .map(|index| {
+            Ok(ValidatorKey {
+                index,
+                secret: bip32_derive(seed, &[44 + HARDENED, 60 + HARDENED, HARDENED, 0, index])?,
+                bls_secret: eip2333_derive(seed, &[12381, 3600, index, 0, 0]),
+            })
+        })
+        .collect()
+}
+
+/// Writes the account keystore and the BLS vote key of a validator in `dir`, each with its
+/// password file. The vote key is written both as a prysm wallet, which geth opens, and as
+/// an EIP-2335 keystore.
+pub fn write_validator_keys(
+    dir: &Path,
+    key: &ValidatorKey,
+    seed: &[u8],
+    password: &Option<String>,
+    scrypt: &ScryptParams,
+) -> anyhow::Result<ValidatorInfo> {
+    let password = match password {
+        Some(password) => password.clone(),
+        None => hex::encode(&keccak256([seed, b"password", &key.index.to_be_bytes()].concat())[..16]),
+    };
+    let info = key.info();
+    fs::create_dir_all(dir.join("keystore"))?;
+    fs::create_dir_all(dir.join("bls").join("keystore"))?;
+
+    let keystore = KeystoreV3::encrypt(&key.secret, &password, scrypt)?;
+    fs::write(
+        dir.join("keystore").join(format!("{:?}.json", info.address)),
+        serde_json::to_string(&keystore)?,
+    )?;
+    fs::write(dir.join("password.txt"), &password)?;
+
+    let bls_keystore = Keystore::encrypt_bls_key(&key.bls_secret, &password, &key.bls_path(), scrypt)?;
+    fs::write(
+        dir.join("bls").join("keystore").join(format!("keystore-{}.json", key.bls_path().replace('/', "_"))),
+        serde_json::to_string_pretty(&bls_keystore)?,
+    )?;
+    write_bls_wallet(dir.join(BLS_WALLET_DIR), std::slice::from_ref(&key.bls_secret), &password, scrypt)?;
+    fs::write(dir.join("bls").join("password.txt"), &password)?;
+    Ok(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethereum_types::U256;
+    use hex_literal::hex;
+
+    #[test]
+    fn derive_known_keys() {
+        // the accounts of hardhat and anvil
+        let seed = mnemonic_to_seed("test test test test test test test test test test test junk", "");
+        let keys = derive_validator_keys(&seed, 2).unwrap();
+        assert_eq!(Address::from(hex!("f39fd6e51aad88f6f4ce6ab8827279cfffb92266")), keys[0].address());
+        assert_eq!(Address::from(hex!("70997970c51812dc3a010c7d01b50e0d17dc79c8")), keys[1].address());
+
+        // EIP-2333 test case 0
+        let seed = hex!("c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1c92f2cf141630c7a3c4ab7c81b2f001698e7463b04");
+        let to_bytes = |dec: &str| {
+            let mut b = [0u8; 32];
+            U256::from_dec_str(dec).unwrap().to_big_endian(&mut b);
+            b
+        };
+        assert_eq!(
+            to_bytes("6083874454709270928345386274498605044986640685124978867557563392430687146096"),
+            eip2333_derive(&seed, &[]).to_bytes()
+        );
+        assert_eq!(
+            to_bytes("20397789859736650942317412262472558107875392172444076792671091975210932703118"),
+            eip2333_derive(&seed, &[0]).to_bytes()
+        );
+    }
+
+    #[test]
+    fn write_geth_bls_wallet() {
+        let dir = std::env::temp_dir().join(format!("akula-tools-validator-keys-{}", std::process::id()));
+        let key = &derive_validator_keys(&[7; 32], 1).unwrap()[0];
+        let scrypt = ScryptParams { n: 1 << 4, r: 8, p: 1 };
+        let info = write_validator_keys(&dir, key, &[7; 32], &Some(String::from("devnet")), &scrypt).unwrap();
+
+        let password = read_password_file(dir.join("bls").join("password.txt")).unwrap();
+        let secrets = read_bls_wallet(dir.join(BLS_WALLET_DIR), &password).unwrap();
+        assert_eq!(vec![info.bls_pub_key], secrets.iter().map(bls_public).collect::<Vec<_>>());
+        assert!(dir.join(BLS_WALLET_DIR).join(WALLET_ACCOUNTS_KEYSTORE).is_file());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,7 @@
+pub mod cluster;
+pub mod genesis;
+pub mod keygen;
+
+pub use cluster::*;
+pub use genesis::*;
+pub use keygen::*;
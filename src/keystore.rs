@@ -0,0 +1,518 @@
+use crate::{crypto::*, models::bls::*};
+use ethereum_types::Address;
+use aes::cipher::{KeyIvInit, StreamCipher};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{fs, path::Path};
+use unicode_normalization::UnicodeNormalization;
+
+type Aes128Ctr = ctr::Ctr128BE<aes::Aes128>;
+
+/// Keystore of the accounts of a prysm wallet, the wallet geth's BLSWalletDir points to
+pub const WALLET_ACCOUNTS_KEYSTORE: &str = "direct/accounts/all-accounts.keystore.json";
+
+/// Options of the keymanager of a prysm wallet, marking it as a direct (imported keys) one
+pub const WALLET_KEYMANAGER_OPTS: &str = "direct/keymanageropts.json";
+
+#[derive(Debug)]
+pub enum KeystoreError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Hex(hex::FromHexError),
+    UnsupportedKdf(String),
+    UnsupportedCipher(String),
+    UnsupportedChecksum(String),
+    WrongPassword,
+    InvalidSecretKey,
+    PublicKeyMismatch { expected: BLSPublicKey, got: BLSPublicKey },
+    NoKeys,
+}
+
+impl std::fmt::Display for KeystoreError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for KeystoreError {}
+
+impl From<std::io::Error> for KeystoreError {
+    fn from(e: std::io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for KeystoreError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::Json(e)
+    }
+}
+
+impl From<hex::FromHexError> for KeystoreError {
+    fn from(e: hex::FromHexError) -> Self {
+        Self::Hex(e)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum KdfParams {
+    Scrypt {
+        dklen: usize,
+        n: u32,
+        r: u32,
+        p: u32,
+        salt: String,
+    },
+    Pbkdf2 {
+        dklen: usize,
+        c: u32,
+        prf: String,
+        salt: String,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CipherParams {
+    pub iv: String,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmptyParams {}
+
+/// One step of the keystore crypto: a function, its parameters and its hex encoded output.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreModule<P> {
+    pub function: String,
+    pub params: P,
+    pub message: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreCrypto {
+    pub kdf: KeystoreModule<KdfParams>,
+    pub checksum: KeystoreModule<EmptyParams>,
+    pub cipher: KeystoreModule<CipherParams>,
+}
+
+/// EIP-2335 keystore, as written by the staking deposit cli. Prysm wallets encrypt their
+/// accounts in the same format, without pubkey or path.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Keystore {
+    pub crypto: KeystoreCrypto,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pubkey: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    #[serde(alias = "id")]
+    pub uuid: String,
+    pub version: u32,
+}
+
+/// Decrypted accounts of a prysm wallet, Go byte slices in base64.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WalletAccounts {
+    pub private_keys: Vec<String>,
+    pub public_keys: Vec<String>,
+}
+
+/// EIP-2335 password processing: NFKD normalization, without control codes.
+fn normalize_password(password: &str) -> Vec<u8> {
+    password
+        .nfkd()
+        .filter(|c| !c.is_control())
+        .collect::<String>()
+        .into_bytes()
+}
+
+/// Parameters of scrypt when encrypting a keystore
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ScryptParams {
+    pub n: u32,
+    pub r: u32,
+    pub p: u32,
+}
+
+/// geth's standard scrypt parameters, also the ones of EIP-2335
+pub const STANDARD_SCRYPT: ScryptParams = ScryptParams { n: 1 << 18, r: 8, p: 1 };
+/// geth's light scrypt parameters, for throwaway devnet keys
+pub const LIGHT_SCRYPT: ScryptParams = ScryptParams { n: 1 << 12, r: 8, p: 6 };
+
+fn derive_key(function: &str, params: &KdfParams, password: &[u8]) -> Result<Vec<u8>, KeystoreError> {
+    match (function, params) {
+        ("scrypt", KdfParams::Scrypt { dklen, n, r, p, salt }) => {
+            if !n.is_power_of_two() {
+                return Err(KeystoreError::UnsupportedKdf(function.to_string()));
+            }
+            let mut key = vec![0u8; *dklen];
+            let params = scrypt::Params::new(n.trailing_zeros() as u8, *r, *p)
+                .map_err(|_| KeystoreError::UnsupportedKdf(function.to_string()))?;
+            scrypt::scrypt(password, &hex::decode(salt)?, &params, &mut key)
+                .map_err(|_| KeystoreError::UnsupportedKdf(function.to_string()))?;
+            Ok(key)
+        }
+        ("pbkdf2", KdfParams::Pbkdf2 { dklen, c, prf, salt }) if prf == "hmac-sha256" => {
+            let mut key = vec![0u8; *dklen];
+            pbkdf2::pbkdf2::<hmac::Hmac<Sha256>>(password, &hex::decode(salt)?, *c, &mut key);
+            Ok(key)
+        }
+        _ => Err(KeystoreError::UnsupportedKdf(function.to_string())),
+    }
+}
+
+fn aes_128_ctr(key: &[u8], iv: &str, data: &mut [u8]) -> Result<(), KeystoreError> {
+    let iv = hex::decode(iv)?;
+    if key.len() < 16 || iv.len() != 16 {
+        return Err(KeystoreError::UnsupportedCipher(String::from("aes-128-ctr")));
+    }
+    Aes128Ctr::new(key[..16].into(), iv[..].into()).apply_keystream(data);
+    Ok(())
+}
+
+/// Random scrypt parameters and cipher iv to encrypt a new keystore with.
+fn new_encryption(scrypt: &ScryptParams) -> (KdfParams, CipherParams) {
+    let salt: [u8; 32] = rand::random();
+    let iv: [u8; 16] = rand::random();
+    let kdf = KdfParams::Scrypt {
+        dklen: 32,
+        n: scrypt.n,
+        r: scrypt.r,
+        p: scrypt.p,
+        salt: hex::encode(salt),
+    };
+    (kdf, CipherParams { iv: hex::encode(iv) })
+}
+
+/// Random version 4 UUID
+fn new_uuid() -> String {
+    let mut b: [u8; 16] = rand::random();
+    b[6] = (b[6] & 0x0f) | 0x40;
+    b[8] = (b[8] & 0x3f) | 0x80;
+    let h = hex::encode(b);
+    format!("{}-{}-{}-{}-{}", &h[..8], &h[8..12], &h[12..16], &h[16..20], &h[20..])
+}
+
+impl Keystore {
+    /// encrypt_bls_key writes an EIP-2335 keystore of a BLS secret key derived at `path`.
+    pub fn encrypt_bls_key(
+        secret: &blst::min_pk::SecretKey,
+        password: &str,
+        path: &str,
+        scrypt: &ScryptParams,
+    ) -> Result<Self, KeystoreError> {
+        Ok(Self {
+            pubkey: Some(hex::encode(bls_public(secret))),
+            path: Some(path.to_string()),
+            ..Self::encrypt(&secret.to_bytes(), password, scrypt)?
+        })
+    }
+
+    /// encrypt writes an EIP-2335 keystore of any secret, without pubkey or path.
+    pub fn encrypt(secret: &[u8], password: &str, scrypt: &ScryptParams) -> Result<Self, KeystoreError> {
+        let (kdf, cipher) = new_encryption(scrypt);
+        let key = derive_key("scrypt", &kdf, &normalize_password(password))?;
+        let mut message = secret.to_vec();
+        aes_128_ctr(&key, &cipher.iv, &mut message)?;
+        let checksum = Sha256::new().chain_update(&key[16..32]).chain_update(&message).finalize();
+        Ok(Self {
+            crypto: KeystoreCrypto {
+                kdf: KeystoreModule { function: String::from("scrypt"), params: kdf, message: String::new() },
+                checksum: KeystoreModule {
+                    function: String::from("sha256"),
+                    params: EmptyParams {},
+                    message: hex::encode(checksum),
+                },
+                cipher: KeystoreModule {
+                    function: String::from("aes-128-ctr"),
+                    params: cipher,
+                    message: hex::encode(message),
+                },
+            },
+            description: None,
+            name: None,
+            pubkey: None,
+            path: None,
+            uuid: new_uuid(),
+            version: 4,
+        })
+    }
+
+    /// decrypt returns the secret the keystore holds, after checking the password against
+    /// the checksum.
+    pub fn decrypt(&self, password: &str) -> Result<Vec<u8>, KeystoreError> {
+        let crypto = &self.crypto;
+        if crypto.checksum.function != "sha256" {
+            return Err(KeystoreError::UnsupportedChecksum(crypto.checksum.function.clone()));
+        }
+        if crypto.cipher.function != "aes-128-ctr" {
+            return Err(KeystoreError::UnsupportedCipher(crypto.cipher.function.clone()));
+        }
+
+        let key = derive_key(&crypto.kdf.function, &crypto.kdf.params, &normalize_password(password))?;
+        if key.len() < 32 {
+            return Err(KeystoreError::UnsupportedKdf(crypto.kdf.function.clone()));
+        }
+        let mut secret = hex::decode(&crypto.cipher.message)?;
+        let checksum = Sha256::new()
+            .chain_update(&key[16..32])
+            .chain_update(&secret)
+            .finalize();
+        if hex::decode(&crypto.checksum.message)? != checksum.as_slice() {
+            return Err(KeystoreError::WrongPassword);
+        }
+
+        aes_128_ctr(&key, &crypto.cipher.params.iv, &mut secret)?;
+        Ok(secret)
+    }
+
+    /// decrypt_bls_key decrypts a BLS secret key and checks it against the keystore pubkey.
+    pub fn decrypt_bls_key(&self, password: &str) -> Result<blst::min_pk::SecretKey, KeystoreError> {
+        let secret = blst::min_pk::SecretKey::from_bytes(&self.decrypt(password)?)
+            .map_err(|_| KeystoreError::InvalidSecretKey)?;
+        if let Some(pubkey) = &self.pubkey {
+            let expected = hex::decode(pubkey.trim_start_matches("0x"))?;
+            if expected.len() != BLS_PUBLIC_KEY_LEN {
+                return Err(KeystoreError::Hex(hex::FromHexError::InvalidStringLength));
+            }
+            let expected = BLSPublicKey::from_slice(&expected);
+            let got = bls_public(&secret);
+            if expected != got {
+                return Err(KeystoreError::PublicKeyMismatch { expected, got });
+            }
+        }
+        Ok(secret)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreV3Crypto {
+    pub cipher: String,
+    pub ciphertext: String,
+    pub cipherparams: CipherParams,
+    pub kdf: String,
+    pub kdfparams: KdfParams,
+    pub mac: String,
+}
+
+/// Version 3 keystore of an account key, the format of geth's keystore dir.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeystoreV3 {
+    pub address: String,
+    pub crypto: KeystoreV3Crypto,
+    pub id: String,
+    pub version: u32,
+}
+
+impl KeystoreV3 {
+    pub fn encrypt(
+        secret: &secp256k1::SecretKey,
+        password: &str,
+        scrypt: &ScryptParams,
+    ) -> Result<Self, KeystoreError> {
+        let (kdf, cipher) = new_encryption(scrypt);
+        let key = derive_key("scrypt", &kdf, password.as_bytes())?;
+        let mut ciphertext = secret.secret_bytes().to_vec();
+        aes_128_ctr(&key, &cipher.iv, &mut ciphertext)?;
+        let public = secp256k1::PublicKey::from_secret_key(secp256k1::SECP256K1, secret);
+        Ok(Self {
+            address: hex::encode(public_to_address(&public)),
+            crypto: KeystoreV3Crypto {
+                cipher: String::from("aes-128-ctr"),
+                mac: hex::encode(keccak256([&key[16..32], &ciphertext[..]].concat())),
+                ciphertext: hex::encode(ciphertext),
+                cipherparams: cipher,
+                kdf: String::from("scrypt"),
+                kdfparams: kdf,
+            },
+            id: new_uuid(),
+            version: 3,
+        })
+    }
+
+    pub fn decrypt(&self, password: &str) -> Result<secp256k1::SecretKey, KeystoreError> {
+        let crypto = &self.crypto;
+        if crypto.cipher != "aes-128-ctr" {
+            return Err(KeystoreError::UnsupportedCipher(crypto.cipher.clone()));
+        }
+        let key = derive_key(&crypto.kdf, &crypto.kdfparams, password.as_bytes())?;
+        if key.len() < 32 {
+            return Err(KeystoreError::UnsupportedKdf(crypto.kdf.clone()));
+        }
+        let mut secret = hex::decode(&crypto.ciphertext)?;
+        if hex::decode(&crypto.mac)? != keccak256([&key[16..32], &secret[..]].concat()).as_bytes() {
+            return Err(KeystoreError::WrongPassword);
+        }
+        aes_128_ctr(&key, &crypto.cipherparams.iv, &mut secret)?;
+        secp256k1::SecretKey::from_slice(&secret).map_err(|_| KeystoreError::InvalidSecretKey)
+    }
+}
+
+/// Reads the password file of a wallet, trailing line breaks are not part of the password.
+pub fn read_password_file(path: impl AsRef<Path>) -> Result<String, KeystoreError> {
+    Ok(fs::read_to_string(path)?.trim_end_matches(&['\r', '\n'][..]).to_string())
+}
+
+/// Addresses of the accounts in a geth keystore dir, read from their keystores without
+/// decrypting them. Files that are not V3 keystores are skipped.
+pub fn read_keystore_addresses(dir: impl AsRef<Path>) -> Result<Vec<Address>, KeystoreError> {
+    let mut paths = fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+    paths.sort();
+    let mut addresses = Vec::new();
+    for path in paths.iter().filter(|p| p.is_file()) {
+        let keystore: KeystoreV3 = match serde_json::from_str(&fs::read_to_string(path)?) {
+            Ok(keystore) => keystore,
+            Err(_) => continue,
+        };
+        let address = hex::decode(keystore.address.trim_start_matches("0x"))?;
+        if address.len() == Address::len_bytes() {
+            addresses.push(Address::from_slice(&address));
+        }
+    }
+    Ok(addresses)
+}
+
+/// read_bls_wallet decrypts the BLS vote keys of a validator: the accounts of a prysm wallet
+/// if `dir` is one, or else every EIP-2335 keystore `.json` file in `dir`.
+pub fn read_bls_wallet(
+    dir: impl AsRef<Path>,
+    password: &str,
+) -> Result<Vec<blst::min_pk::SecretKey>, KeystoreError> {
+    let dir = dir.as_ref();
+    let accounts = dir.join(WALLET_ACCOUNTS_KEYSTORE);
+    let mut secrets = Vec::new();
+    if accounts.is_file() {
+        let keystore: Keystore = serde_json::from_str(&fs::read_to_string(accounts)?)?;
+        let accounts: WalletAccounts = serde_json::from_slice(&keystore.decrypt(password)?)?;
+        for private_key in &accounts.private_keys {
+            let private_key = base64::decode(private_key).map_err(|_| KeystoreError::InvalidSecretKey)?;
+            secrets.push(
+                blst::min_pk::SecretKey::from_bytes(&private_key).map_err(|_| KeystoreError::InvalidSecretKey)?,
+            );
+        }
+    } else {
+        let mut paths = fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<Vec<_>, _>>()?;
+        paths.sort();
+        for path in paths.iter().filter(|p| p.extension().map_or(false, |e| e == "json")) {
+            let keystore: Keystore = serde_json::from_str(&fs::read_to_string(path)?)?;
+            secrets.push(keystore.decrypt_bls_key(password)?);
+        }
+    }
+    if secrets.is_empty() {
+        return Err(KeystoreError::NoKeys);
+    }
+    Ok(secrets)
+}
+
+/// write_bls_wallet lays out a prysm wallet in `dir` holding the BLS vote keys, the way
+/// `geth bls wallet create` and `geth bls account import` do, for geth's BLSWalletDir.
+pub fn write_bls_wallet(
+    dir: impl AsRef<Path>,
+    secrets: &[blst::min_pk::SecretKey],
+    password: &str,
+    scrypt: &ScryptParams,
+) -> Result<(), KeystoreError> {
+    let dir = dir.as_ref();
+    let accounts = WalletAccounts {
+        private_keys: secrets.iter().map(|secret| base64::encode(secret.to_bytes())).collect(),
+        public_keys: secrets.iter().map(|secret| base64::encode(bls_public(secret))).collect(),
+    };
+    let keystore = Keystore {
+        name: Some(String::from("keystore")),
+        ..Keystore::encrypt(&serde_json::to_vec(&accounts)?, password, scrypt)?
+    };
+    let path = dir.join(WALLET_ACCOUNTS_KEYSTORE);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, serde_json::to_string(&keystore)?)?;
+    fs::write(dir.join(WALLET_KEYMANAGER_OPTS), r#"{"direct_eip_version":"EIP-2335"}"#)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    /// PBKDF2 test vector of EIP-2335
+    const PBKDF2_KEYSTORE: &str = r#"{
+        "crypto": {
+            "kdf": {
+                "function": "pbkdf2",
+                "params": {
+                    "dklen": 32,
+                    "c": 262144,
+                    "prf": "hmac-sha256",
+                    "salt": "d4e56740f876aef8c010b86a40d5f56745a118d0906a34e69aec8c0db1cb8fa3"
+                },
+                "message": ""
+            },
+            "checksum": {
+                "function": "sha256",
+                "params": {},
+                "message": "8a9f5d9912ed7e75ea794bc5a89bca5f193721d30868ade6f73043c6ea6febf1"
+            },
+            "cipher": {
+                "function": "aes-128-ctr",
+                "params": {
+                    "iv": "264daa3f303d7259501c93d997d84fe6"
+                },
+                "message": "cee03fde2af33149775b7223e7845e4fb2c8ae1792e5f99fe9ecf474cc8c16ad"
+            }
+        },
+        "description": "This is a test keystore that uses PBKDF2 to secure the secret.",
+        "pubkey": "9612d7a727c9d0a22e185a1c768478dfe919cada9266988cb32359c11f2b7b27f4ae4040902382ae2910c15e2b420d07",
+        "path": "m/12381/60/0/0",
+        "uuid": "64625def-3331-4eea-ab6f-782f3ed16a83",
+        "version": 4
+    }"#;
+
+    #[test]
+    fn decrypt_eip2335_keystore() {
+        let keystore: Keystore = serde_json::from_str(PBKDF2_KEYSTORE).unwrap();
+        let password = "\u{1d531}\u{1d522}\u{1d530}\u{1d531}\u{1d52d}\u{1d51e}\u{1d530}\u{1d530}\u{1d534}\u{1d52c}\u{1d52f}\u{1d521}\u{1f511}";
+        assert_eq!(
+            hex!("000000000019d6689c085ae165831e934ff763ae46a2a6c172b3f1b60a8ce26f"),
+            keystore.decrypt_bls_key(password).unwrap().to_bytes()
+        );
+        assert!(matches!(keystore.decrypt("testpassword"), Err(KeystoreError::WrongPassword)));
+    }
+
+    #[test]
+    fn keystore_roundtrip() {
+        let scrypt = ScryptParams { n: 1 << 4, r: 8, p: 1 };
+        let bls_secret = blst::min_pk::SecretKey::key_gen(&[1; 32], &[]).unwrap();
+        let keystore = Keystore::encrypt_bls_key(&bls_secret, "devnet", "m/12381/3600/0/0/0", &scrypt).unwrap();
+        let keystore: Keystore = serde_json::from_str(&serde_json::to_string(&keystore).unwrap()).unwrap();
+        assert_eq!(bls_secret.to_bytes(), keystore.decrypt_bls_key("devnet").unwrap().to_bytes());
+
+        let secret = secp256k1::SecretKey::from_slice(&[1; 32]).unwrap();
+        let keystore = KeystoreV3::encrypt(&secret, "devnet", &scrypt).unwrap();
+        assert_eq!(secret, keystore.decrypt("devnet").unwrap());
+        assert!(matches!(keystore.decrypt("mainnet"), Err(KeystoreError::WrongPassword)));
+    }
+
+    #[test]
+    fn bls_wallet_roundtrip() {
+        let scrypt = ScryptParams { n: 1 << 4, r: 8, p: 1 };
+        let dir = std::env::temp_dir().join(format!("akula-tools-bls-wallet-{}", std::process::id()));
+        let secrets: Vec<_> = (1..=2u8).map(|i| blst::min_pk::SecretKey::key_gen(&[i; 32], &[]).unwrap()).collect();
+        write_bls_wallet(&dir, &secrets, "devnet", &scrypt).unwrap();
+
+        let read = read_bls_wallet(&dir, "devnet").unwrap();
+        assert_eq!(
+            secrets.iter().map(|s| s.to_bytes()).collect::<Vec<_>>(),
+            read.iter().map(|s| s.to_bytes()).collect::<Vec<_>>()
+        );
+        assert!(matches!(read_bls_wallet(&dir, "mainnet"), Err(KeystoreError::WrongPassword)));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -2,6 +2,13 @@
 step_trait
 )]
 
+pub mod consensus;
+pub mod convert;
+pub mod crypto;
+pub mod devnet;
+pub mod keystore;
 pub mod models;
+pub mod p2p;
+pub mod systemcontracts;
 pub(crate) mod util;
 
@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use serde::{Deserialize, Serialize};
 use bytes::{Bytes};
 use ethereum_types::{Address, H256, U256};
@@ -7,115 +7,325 @@ use crate::models::chainspec::BlockNumber;
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct ParliaConfig {
+    #[serde(with = "numeric")]
     pub period: u64,
+    #[serde(with = "numeric")]
     pub epoch: u64,
 }
 
 #[derive(Clone, Copy, Debug, Deserialize, Serialize)]
 pub struct ChainConfig {
-    #[serde(rename = "chainId")]
+    #[serde(rename = "chainId", with = "numeric")]
     pub chain_id: u64,
-    #[serde(rename = "homesteadBlock")]
+    #[serde(rename = "homesteadBlock", default, with = "numeric::option")]
     pub homestead_block: Option<BlockNumber>,
-    #[serde(rename = "eip150Block")]
+    #[serde(rename = "eip150Block", default, with = "numeric::option")]
     pub eip_150_block: Option<BlockNumber>,
     #[serde(rename = "eip150Hash")]
     pub eip_150_hash: H256,
-    #[serde(rename = "eip155Block")]
+    #[serde(rename = "eip155Block", default, with = "numeric::option")]
     pub eip_155_block: Option<BlockNumber>,
-    #[serde(rename = "eip158Block")]
+    #[serde(rename = "eip158Block", default, with = "numeric::option")]
     pub eip_158_block: Option<BlockNumber>,
-    #[serde(rename = "byzantiumBlock")]
+    #[serde(rename = "byzantiumBlock", default, with = "numeric::option")]
     pub byzantium_block: Option<BlockNumber>,
-    #[serde(rename = "constantinopleBlock")]
+    #[serde(rename = "constantinopleBlock", default, with = "numeric::option")]
     pub constantinople_block: Option<BlockNumber>,
-    #[serde(rename = "petersburgBlock")]
+    #[serde(rename = "petersburgBlock", default, with = "numeric::option")]
     pub petersburg_block: Option<BlockNumber>,
-    #[serde(rename = "istanbulBlock")]
+    #[serde(rename = "istanbulBlock", default, with = "numeric::option")]
     pub istanbul_block: Option<BlockNumber>,
-    #[serde(rename = "muirGlacierBlock")]
+    #[serde(rename = "muirGlacierBlock", default, with = "numeric::option")]
     pub muir_glacier_block: Option<BlockNumber>,
-    #[serde(rename = "ramanujanBlock")]
+    #[serde(rename = "ramanujanBlock", default, with = "numeric::option")]
     pub ramanujan_block: Option<BlockNumber>,
-    #[serde(rename = "nielsBlock")]
+    #[serde(rename = "nielsBlock", default, with = "numeric::option")]
     pub niels_block: Option<BlockNumber>,
-    #[serde(rename = "eulerBlock")]
+    #[serde(rename = "eulerBlock", default, with = "numeric::option")]
     pub euler_block: Option<BlockNumber>,
-    #[serde(rename = "brunoBlock")]
+    #[serde(rename = "brunoBlock", default, with = "numeric::option")]
     pub bruno_block: Option<BlockNumber>,
-    #[serde(rename = "mirrorSyncBlock")]
+    #[serde(rename = "mirrorSyncBlock", default, with = "numeric::option")]
     pub mirror_sync_block: Option<BlockNumber>,
-    #[serde(rename = "berlinBlock")]
+    #[serde(rename = "berlinBlock", default, with = "numeric::option")]
     pub berlin_block: Option<BlockNumber>,
-    #[serde(rename = "londonBlock")]
+    #[serde(rename = "londonBlock", default, with = "numeric::option")]
     pub london_block: Option<BlockNumber>,
-    #[serde(rename = "gibbsBlock")]
+    #[serde(rename = "gibbsBlock", default, with = "numeric::option")]
     pub gibbs_block: Option<BlockNumber>,
-    #[serde(rename = "bonehBlock")]
+    #[serde(rename = "bonehBlock", default, with = "numeric::option")]
     pub boneh_block: Option<BlockNumber>,
-    #[serde(rename = "lynnBlock")]
+    #[serde(rename = "moranBlock", default, with = "numeric::option")]
+    pub moran_block: Option<BlockNumber>,
+    #[serde(rename = "planckBlock", default, with = "numeric::option")]
+    pub planck_block: Option<BlockNumber>,
+    #[serde(rename = "lynnBlock", default, with = "numeric::option")]
     pub lynn_block: Option<BlockNumber>,
+    #[serde(rename = "hertzBlock", default, with = "numeric::option")]
+    pub hertz_block: Option<BlockNumber>,
+    #[serde(rename = "feynmanTime", default, with = "numeric::option")]
+    pub feynman_time: Option<u64>,
+    #[serde(rename = "haberTime", default, with = "numeric::option")]
+    pub haber_time: Option<u64>,
     #[serde(rename = "parlia")]
     pub parlia: ParliaConfig,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct GenesisAccount {
-    pub balance: String,
+    #[serde(with = "numeric")]
+    pub balance: U256,
     pub code: Option<String>,
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct Genesis {
     pub config: ChainConfig,
-    pub number: U256,
+    #[serde(deserialize_with = "numeric::deserialize", serialize_with = "hexu64::serialize")]
+    pub number: u64,
     #[serde(rename = "parentHash")]
     pub parent_hash: H256,
-    pub nonce: U256,
-    pub timestamp: U256,
+    #[serde(deserialize_with = "numeric::deserialize", serialize_with = "hexu64::serialize")]
+    pub nonce: u64,
+    #[serde(deserialize_with = "numeric::deserialize", serialize_with = "hexu64::serialize")]
+    pub timestamp: u64,
     #[serde(rename = "extraData", with = "hexbytes")]
     pub extra_data: Bytes,
-    #[serde(rename = "gasLimit")]
-    pub gas_limit: U256,
-    #[serde(rename = "gasUsed")]
-    pub gas_used: U256,
+    #[serde(rename = "gasLimit", deserialize_with = "numeric::deserialize", serialize_with = "hexu64::serialize")]
+    pub gas_limit: u64,
+    #[serde(rename = "gasUsed", deserialize_with = "numeric::deserialize", serialize_with = "hexu64::serialize")]
+    pub gas_used: u64,
+    #[serde(with = "numeric")]
     pub difficulty: U256,
     #[serde(rename = "mixHash")]
     pub mix_hash: H256,
     pub coinbase: Address,
-    pub alloc: HashMap<Address, GenesisAccount>,
+    pub alloc: BTreeMap<Address, GenesisAccount>,
+    /// base fee of the genesis block if London is on at genesis, BSC's default otherwise
+    #[serde(rename = "baseFeePerGas", default, with = "numeric::option")]
+    pub base_fee_per_gas: Option<U256>,
 }
 
+fn default_max_peers() -> u64 {
+    50
+}
+
+fn default_listen_addr() -> String {
+    String::from(":30303")
+}
+
+/// `[Node.P2P]` of a geth config.toml, geth's defaults standing in for the missing keys
 #[derive(Clone, Debug, Deserialize, Serialize)]
 pub struct TomlP2P {
-    #[serde(rename = "MaxPeers")]
+    #[serde(rename = "MaxPeers", default = "default_max_peers")]
     pub max_peers: u64,
-    #[serde(rename = "NoDiscovery")]
+    #[serde(rename = "NoDiscovery", default)]
     pub no_discovery: bool,
-    #[serde(rename = "StaticNodes")]
+    #[serde(rename = "StaticNodes", default)]
     pub static_nodes: Vec<String>,
-    #[serde(rename = "TrustedNodes")]
+    #[serde(rename = "TrustedNodes", default)]
     pub trusted_nodes: Vec<String>,
-    #[serde(rename = "ListenAddr")]
+    #[serde(rename = "BootstrapNodes", default)]
+    pub bootstrap_nodes: Vec<String>,
+    #[serde(rename = "BootstrapNodesV5", default)]
+    pub bootstrap_nodes_v5: Vec<String>,
+    #[serde(rename = "ListenAddr", default = "default_listen_addr")]
     pub listen_addr: String,
-    #[serde(rename = "EnableMsgEvents")]
+    #[serde(rename = "EnableMsgEvents", default)]
     pub enable_msg_events: bool,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+impl Default for TomlP2P {
+    fn default() -> Self {
+        Self {
+            max_peers: default_max_peers(),
+            no_discovery: false,
+            static_nodes: vec![],
+            trusted_nodes: vec![],
+            bootstrap_nodes: vec![],
+            bootstrap_nodes_v5: vec![],
+            listen_addr: default_listen_addr(),
+            enable_msg_events: false,
+        }
+    }
+}
+
+/// `[Node.HTTPTimeouts]` of a geth config.toml, in nanoseconds
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TomlHTTPTimeouts {
+    #[serde(rename = "ReadTimeout", skip_serializing_if = "Option::is_none")]
+    pub read_timeout: Option<u64>,
+    #[serde(rename = "WriteTimeout", skip_serializing_if = "Option::is_none")]
+    pub write_timeout: Option<u64>,
+    #[serde(rename = "IdleTimeout", skip_serializing_if = "Option::is_none")]
+    pub idle_timeout: Option<u64>,
+}
+
+/// `[Node.LogConfig]` of a BSC geth config.toml
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TomlLogConfig {
+    #[serde(rename = "FilePath", skip_serializing_if = "Option::is_none")]
+    pub file_path: Option<String>,
+    #[serde(rename = "MaxBytesSize", skip_serializing_if = "Option::is_none")]
+    pub max_bytes_size: Option<u64>,
+    #[serde(rename = "Level", skip_serializing_if = "Option::is_none")]
+    pub level: Option<String>,
+    #[serde(rename = "FileRoot", skip_serializing_if = "Option::is_none")]
+    pub file_root: Option<String>,
+    #[serde(rename = "RotateHours", skip_serializing_if = "Option::is_none")]
+    pub rotate_hours: Option<u64>,
+    #[serde(rename = "MaxBackups", skip_serializing_if = "Option::is_none")]
+    pub max_backups: Option<u64>,
+}
+
+/// `[Node]` of a geth config.toml, its keys being specific to each node
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct TomlNode {
-    #[serde(rename = "BLSPasswordFile")]
-    pub bls_password_file: String,
-    #[serde(rename = "BLSWalletDir")]
-    pub bls_wallet_dir: String,
-    #[serde(rename = "P2P")]
+    #[serde(rename = "DataDir", skip_serializing_if = "Option::is_none")]
+    pub data_dir: Option<String>,
+    #[serde(rename = "KeyStoreDir", skip_serializing_if = "Option::is_none")]
+    pub key_store_dir: Option<String>,
+    #[serde(rename = "IPCPath", skip_serializing_if = "Option::is_none")]
+    pub ipc_path: Option<String>,
+    #[serde(rename = "HTTPHost", skip_serializing_if = "Option::is_none")]
+    pub http_host: Option<String>,
+    #[serde(rename = "HTTPPort", skip_serializing_if = "Option::is_none")]
+    pub http_port: Option<u16>,
+    #[serde(rename = "HTTPCors", skip_serializing_if = "Option::is_none")]
+    pub http_cors: Option<Vec<String>>,
+    #[serde(rename = "HTTPVirtualHosts", skip_serializing_if = "Option::is_none")]
+    pub http_virtual_hosts: Option<Vec<String>>,
+    #[serde(rename = "HTTPModules", skip_serializing_if = "Option::is_none")]
+    pub http_modules: Option<Vec<String>>,
+    #[serde(rename = "WSHost", skip_serializing_if = "Option::is_none")]
+    pub ws_host: Option<String>,
+    #[serde(rename = "WSPort", skip_serializing_if = "Option::is_none")]
+    pub ws_port: Option<u16>,
+    #[serde(rename = "WSOrigins", skip_serializing_if = "Option::is_none")]
+    pub ws_origins: Option<Vec<String>>,
+    #[serde(rename = "WSModules", skip_serializing_if = "Option::is_none")]
+    pub ws_modules: Option<Vec<String>>,
+    #[serde(rename = "InsecureUnlockAllowed", skip_serializing_if = "Option::is_none")]
+    pub insecure_unlock_allowed: Option<bool>,
+    #[serde(rename = "NoUSB", skip_serializing_if = "Option::is_none")]
+    pub no_usb: Option<bool>,
+    #[serde(rename = "BLSPasswordFile", skip_serializing_if = "Option::is_none")]
+    pub bls_password_file: Option<String>,
+    #[serde(rename = "BLSWalletDir", skip_serializing_if = "Option::is_none")]
+    pub bls_wallet_dir: Option<String>,
+    #[serde(rename = "P2P", default)]
     pub p2p: TomlP2P,
+    #[serde(rename = "HTTPTimeouts", skip_serializing_if = "Option::is_none")]
+    pub http_timeouts: Option<TomlHTTPTimeouts>,
+    #[serde(rename = "LogConfig", skip_serializing_if = "Option::is_none")]
+    pub log_config: Option<TomlLogConfig>,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
+/// `[Eth]` of a geth config.toml, the chain and sync settings of a node
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TomlEth {
+    /// network id of the eth protocol, the chain id unless set
+    #[serde(rename = "NetworkId", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub network_id: Option<u64>,
+    #[serde(rename = "SyncMode", skip_serializing_if = "Option::is_none")]
+    pub sync_mode: Option<String>,
+    /// DNS discovery trees of eth peers, as `enrtree://` URLs
+    #[serde(rename = "EthDiscoveryURLs", default)]
+    pub eth_discovery_urls: Vec<String>,
+    #[serde(rename = "SnapDiscoveryURLs", default)]
+    pub snap_discovery_urls: Vec<String>,
+    #[serde(rename = "NoPruning", skip_serializing_if = "Option::is_none")]
+    pub no_pruning: Option<bool>,
+    #[serde(rename = "TxLookupLimit", skip_serializing_if = "Option::is_none")]
+    pub tx_lookup_limit: Option<u64>,
+    #[serde(rename = "DatabaseCache", skip_serializing_if = "Option::is_none")]
+    pub database_cache: Option<u64>,
+    #[serde(rename = "DatabaseFreezer", skip_serializing_if = "Option::is_none")]
+    pub database_freezer: Option<String>,
+    #[serde(rename = "TrieCleanCache", skip_serializing_if = "Option::is_none")]
+    pub trie_clean_cache: Option<u64>,
+    #[serde(rename = "TrieCleanCacheJournal", skip_serializing_if = "Option::is_none")]
+    pub trie_clean_cache_journal: Option<String>,
+    #[serde(rename = "TrieCleanCacheRejournal", skip_serializing_if = "Option::is_none")]
+    pub trie_clean_cache_rejournal: Option<u64>,
+    #[serde(rename = "TrieDirtyCache", skip_serializing_if = "Option::is_none")]
+    pub trie_dirty_cache: Option<u64>,
+    #[serde(rename = "TrieTimeout", skip_serializing_if = "Option::is_none")]
+    pub trie_timeout: Option<u64>,
+    #[serde(rename = "SnapshotCache", skip_serializing_if = "Option::is_none")]
+    pub snapshot_cache: Option<u64>,
+    #[serde(rename = "TriesInMemory", skip_serializing_if = "Option::is_none")]
+    pub tries_in_memory: Option<u64>,
+    #[serde(rename = "Preimages", skip_serializing_if = "Option::is_none")]
+    pub preimages: Option<bool>,
+    #[serde(rename = "RPCGasCap", skip_serializing_if = "Option::is_none")]
+    pub rpc_gas_cap: Option<u64>,
+    /// fork overrides, replacing the block of the fork in the genesis chain config
+    #[serde(rename = "OverrideBerlin", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_berlin: Option<BlockNumber>,
+    #[serde(rename = "OverrideArrowGlacier", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_arrow_glacier: Option<BlockNumber>,
+    #[serde(rename = "OverrideTerminalTotalDifficulty", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_terminal_total_difficulty: Option<U256>,
+    #[serde(rename = "OverrideTerminalTotalDifficultyPassed", skip_serializing_if = "Option::is_none")]
+    pub override_terminal_total_difficulty_passed: Option<bool>,
+    /// time-based fork overrides, as unix timestamps
+    #[serde(rename = "OverrideShanghai", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_shanghai: Option<u64>,
+    #[serde(rename = "OverrideKepler", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_kepler: Option<u64>,
+    #[serde(rename = "OverrideFeynman", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_feynman: Option<u64>,
+    #[serde(rename = "OverrideFeynmanFix", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_feynman_fix: Option<u64>,
+    #[serde(rename = "OverrideCancun", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_cancun: Option<u64>,
+    #[serde(rename = "OverrideHaber", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_haber: Option<u64>,
+    #[serde(rename = "OverrideBohr", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_bohr: Option<u64>,
+    #[serde(rename = "OverridePascal", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_pascal: Option<u64>,
+    #[serde(rename = "OverridePrague", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_prague: Option<u64>,
+    #[serde(rename = "OverrideVerkle", default, with = "numeric::option", skip_serializing_if = "Option::is_none")]
+    pub override_verkle: Option<u64>,
+}
+
+/// `[Metrics]` of a geth config.toml
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TomlMetrics {
+    #[serde(rename = "Enabled", skip_serializing_if = "Option::is_none")]
+    pub enabled: Option<bool>,
+    #[serde(rename = "EnabledExpensive", skip_serializing_if = "Option::is_none")]
+    pub enabled_expensive: Option<bool>,
+    #[serde(rename = "HTTP", skip_serializing_if = "Option::is_none")]
+    pub http: Option<String>,
+    #[serde(rename = "Port", skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(rename = "EnableInfluxDB", skip_serializing_if = "Option::is_none")]
+    pub enable_influx_db: Option<bool>,
+    #[serde(rename = "InfluxDBEndpoint", skip_serializing_if = "Option::is_none")]
+    pub influx_db_endpoint: Option<String>,
+    #[serde(rename = "InfluxDBDatabase", skip_serializing_if = "Option::is_none")]
+    pub influx_db_database: Option<String>,
+    #[serde(rename = "InfluxDBUsername", skip_serializing_if = "Option::is_none")]
+    pub influx_db_username: Option<String>,
+    #[serde(rename = "InfluxDBPassword", skip_serializing_if = "Option::is_none")]
+    pub influx_db_password: Option<String>,
+    #[serde(rename = "InfluxDBTags", skip_serializing_if = "Option::is_none")]
+    pub influx_db_tags: Option<String>,
+}
+
+/// Config of a BSC geth node, as written by `geth dumpconfig`
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct TomlConfig {
-    #[serde(rename = "Node")]
+    #[serde(rename = "Eth", default)]
+    pub eth: TomlEth,
+    #[serde(rename = "Node", default)]
     pub node: TomlNode,
+    #[serde(rename = "Metrics", skip_serializing_if = "Option::is_none")]
+    pub metrics: Option<TomlMetrics>,
 }
 
 #[cfg(test)]
@@ -157,7 +367,12 @@ MaxBytesSize = 10485760
 Level = "info"
 FileRoot = """#;
         let conf: TomlConfig = toml::from_str(s).unwrap();
-        assert_eq!("/Users/jacksen/OtherProjects/bsc-deploy/clusterNode/node1/blspassword.txt", conf.node.bls_password_file);
+        assert_eq!(Some("/Users/jacksen/OtherProjects/bsc-deploy/clusterNode/node1/blspassword.txt"), conf.node.bls_password_file.as_deref());
+        assert_eq!(Some(8502), conf.node.http_port);
+        assert_eq!(Some(120000000000), conf.node.http_timeouts.as_ref().unwrap().idle_timeout);
+        assert_eq!(Some("info"), conf.node.log_config.as_ref().unwrap().level.as_deref());
+        assert_eq!(None, conf.eth.network_id);
+        assert!(conf.metrics.is_none());
         assert_eq!("enode://2e9ca9c58a47a80587c936b1a4846f84aa6ee5b31062321cd56684ff97957e12e3d867800ed9540201554754d1effed3e249d2c11835aba3bfebd0a19018c70d@127.0.0.1:30333", conf.node.p2p.static_nodes[0]);
         assert_eq!("enode://624e893ae9c9f7a1173e0a46501dacabe374f3ffab327e753f496ba2fe6b0e6fb10e90d9e00500623a2451d0bbf9d1e50fbfa7379f5280c71c88ca6ff7c22163@127.0.0.1:30336", conf.node.p2p.static_nodes[3]);
     }
@@ -252,8 +467,74 @@ FileRoot = """#;
 }"#;
         let genesis: Genesis = serde_json::from_str(s).unwrap();
         assert_eq!(200, genesis.config.parlia.epoch);
-        assert_eq!(0, genesis.config.boneh_block.unwrap());
+        assert_eq!(BlockNumber(0), genesis.config.boneh_block.unwrap());
         assert_eq!("000000000000000000000000000000000000000000000000000000000000000005e0190729ea647015ebc85e54aef5a6a31aaa058c85e6972fc98cd3c81d64d40e325acfed44365b97a7567a27939c14dbc7512ddcf54cb1284eb637cfa308ae4e00cb5588a9f40bc72719b8a6ca17bfdbb681241e7edbc3658addebd6ef7609df215e006987040d0a643858f3a4d791beaa77177d67529160e645fac54f0d8acdcd5a088393cb6681df7d472b20084f22e2fd68fac859031b6134595a89abcc45efe76bec679ca35c27adbd66fb9712a278e3c8530ab25cfaf997765aee574f5c5745dbb873dbf7e961684347e828eff34af6960478d6b3dbbfe08c681d8673f3a1484f2b97137fb957daad064ca6cbe5b99549249ceb51f42e928ec091f94fed642ddffe3a9916769538decd0a9937bfd7b7a69df33b2f905a480a8ece0fbc51bdfe0d068b20e24ad933b9af0a55a6d34a08e10b832a10f389154dc0dec79b63a38b79ea2f0d9f4fa664b3c06b1b2437cb58236f0000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000", hex::encode(genesis.extra_data.deref()));
         // println!("{}", serde_json::to_string(&genesis).unwrap());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn parse_flexible_numbers() {
+        let config: ChainConfig = serde_json::from_str(r#"{
+  "chainId": "0x2ca",
+  "homesteadBlock": 0,
+  "eip150Block": "0",
+  "eip150Hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+  "berlinBlock": "31302048",
+  "londonBlock": null,
+  "parlia": {"period": "3", "epoch": "0xc8"}
+}"#).unwrap();
+        assert_eq!(714, config.chain_id);
+        assert_eq!(Some(BlockNumber(0)), config.homestead_block);
+        assert_eq!(Some(BlockNumber(31302048)), config.berlin_block);
+        assert_eq!(None, config.london_block);
+        assert_eq!(None, config.lynn_block);
+        assert_eq!(200, config.parlia.epoch);
+
+        let account: GenesisAccount =
+            serde_json::from_str(r#"{"balance": "500000000000000000000000000"}"#).unwrap();
+        assert_eq!(U256::from_dec_str("500000000000000000000000000").unwrap(), account.balance);
+        let account: GenesisAccount = serde_json::from_str(r#"{"balance": 1000}"#).unwrap();
+        assert_eq!(U256::from(1000), account.balance);
+        // a bare integer past 64 bits keeps every digit, also through a serde_json::Value
+        let json = r#"{"balance": 500000000000000000000000000}"#;
+        let account: GenesisAccount = serde_json::from_str(json).unwrap();
+        assert_eq!(U256::from_dec_str("500000000000000000000000000").unwrap(), account.balance);
+        let value: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(account.balance, serde_json::from_value::<GenesisAccount>(value).unwrap().balance);
+        let err = serde_json::from_str::<GenesisAccount>(&format!(r#"{{"balance": 1{}}}"#, "0".repeat(78))).unwrap_err();
+        assert!(err.to_string().starts_with(&format!("1{} overflows U256", "0".repeat(78))));
+
+        let err = |s: &str| serde_json::from_str::<ParliaConfig>(s).unwrap_err().to_string();
+        assert!(err(r#"{"period": "0x10000000000000000", "epoch": 1}"#).starts_with(r#""0x10000000000000000" overflows u64"#));
+        assert!(err(r#"{"period": "18446744073709551616", "epoch": 1}"#).starts_with(r#""18446744073709551616" overflows u64"#));
+        assert!(err(r#"{"period": -1, "epoch": 1}"#).starts_with("-1 is negative, not a u64"));
+        assert!(err(r#"{"period": "0x", "epoch": 1}"#).starts_with("invalid value"));
+        assert!(err(r#"{"period": 3, "epoch": 1e30}"#).contains("write it as a hex or decimal string"));
+        let err = serde_json::from_str::<GenesisAccount>(&format!(r#"{{"balance": "0x1{}"}}"#, "0".repeat(64))).unwrap_err();
+        assert!(err.to_string().starts_with("\"0x1") && err.to_string().contains("overflows U256"));
+        assert!(serde_json::from_str::<ChainConfig>(r#"{"chainId": 1, "berlinBlock": "0x10000000000000000", "eip150Hash": "0x0000000000000000000000000000000000000000000000000000000000000000", "parlia": {"period": 3, "epoch": 200}}"#)
+            .unwrap_err().to_string().contains("overflows BlockNumber"));
+
+        // genesis header fields are u64 in geth's header, a bigger value is an error, not a panic later on
+        let genesis = |timestamp: &str| {
+            serde_json::from_str::<Genesis>(&format!(
+                r#"{{"config": {{"chainId": 714, "eip150Hash": "0x0000000000000000000000000000000000000000000000000000000000000000", "parlia": {{"period": 3, "epoch": 200}}}},
+  "number": "0x0", "parentHash": "0x0000000000000000000000000000000000000000000000000000000000000000", "nonce": "0x0", "timestamp": {},
+  "extraData": "0x", "gasLimit": 30000000, "gasUsed": "0", "difficulty": "0x1", "mixHash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+  "coinbase": "0x0000000000000000000000000000000000000000", "alloc": {{}}}}"#,
+                timestamp
+            ))
+        };
+        assert_eq!(u64::MAX, genesis(r#""0xffffffffffffffff""#).unwrap().timestamp);
+        let err = genesis(r#""0x10000000000000000""#).unwrap_err().to_string();
+        assert!(err.starts_with(r#""0x10000000000000000" overflows u64"#), "{}", err);
+        let err = genesis("18446744073709551616").unwrap_err().to_string();
+        assert!(err.starts_with("18446744073709551616 overflows u64"), "{}", err);
+        assert_eq!(r#""0x1c9c380""#, serde_json::to_value(genesis("0").unwrap()).unwrap()["gasLimit"].to_string());
+
+        let config: TomlConfig = toml::from_str("[Eth]\nNetworkId = 714\nOverrideBerlin = \"0x64\"\nOverrideTerminalTotalDifficulty = \"58750000000000000000000\"\n").unwrap();
+        assert_eq!(Some(714), config.eth.network_id);
+        assert_eq!(Some(BlockNumber(100)), config.eth.override_berlin);
+        assert_eq!(U256::from_dec_str("58750000000000000000000").ok(), config.eth.override_terminal_total_difficulty);
+    }
+}
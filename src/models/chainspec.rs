@@ -6,7 +6,7 @@ use std::{
     collections::{BTreeMap, BTreeSet},
     time::Duration,
 };
-use ethereum_types::{Address, H256, H64, U256, U64};
+use ethereum_types::{Address, H256, H520, H64, U256, U64};
 pub use ethnum::prelude::*;
 use derive_more::*;
 use fastrlp::*;
@@ -296,6 +296,20 @@ impl ConsensusParams {
             _ => false
         }
     }
+
+    pub fn parlia_period(&self) -> Option<u64> {
+        match self.seal_verification {
+            SealVerificationParams::Parlia { period, .. } => Some(period),
+            _ => None
+        }
+    }
+
+    pub fn parlia_epoch(&self) -> Option<u64> {
+        match self.seal_verification {
+            SealVerificationParams::Parlia { epoch, .. } => Some(epoch),
+            _ => None
+        }
+    }
 }
 
 
@@ -420,6 +434,18 @@ pub struct Upgrades {
     skip_serializing_if = "Option::is_none",
     with = "::serde_with::rust::unwrap_or_skip"
     )]
+    pub moran: Option<BlockNumber>,
+    #[serde(
+    default,
+    skip_serializing_if = "Option::is_none",
+    with = "::serde_with::rust::unwrap_or_skip"
+    )]
+    pub planck: Option<BlockNumber>,
+    #[serde(
+    default,
+    skip_serializing_if = "Option::is_none",
+    with = "::serde_with::rust::unwrap_or_skip"
+    )]
     pub boneh: Option<BlockNumber>,
     #[serde(
     default,
@@ -427,6 +453,12 @@ pub struct Upgrades {
     with = "::serde_with::rust::unwrap_or_skip"
     )]
     pub lynn: Option<BlockNumber>,
+    #[serde(
+    default,
+    skip_serializing_if = "Option::is_none",
+    with = "::serde_with::rust::unwrap_or_skip"
+    )]
+    pub hertz: Option<BlockNumber>,
 }
 
 impl ChainSpec {
@@ -498,6 +530,15 @@ impl ChainSpec {
     pub fn is_lynn(&self, number: &BlockNumber) -> bool {
         is_forked(self.upgrades.lynn, number)
     }
+
+    /// EIP-1559 parameters in force at `number`, none before `eip1559_block`
+    pub fn eip1559_params(&self, number: &BlockNumber) -> Option<&Eip1559Params> {
+        if is_forked(self.consensus.eip1559_block, number) {
+            self.params.eip1559.as_ref()
+        } else {
+            None
+        }
+    }
 }
 
 /// is_forked returns whether a fork scheduled at block s is active at the given head block.
@@ -526,12 +567,51 @@ pub fn is_on_forked(fork_op: Option<BlockNumber>, current: &BlockNumber) -> bool
     }
 }
 
+/// Where the base fee of a transaction goes
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BaseFeeRecipient {
+    /// burned, as on Ethereum
+    Burn,
+    /// paid to the system account with the tip, from where Parlia shares the fees of a
+    /// block between its validator and the SystemReward contract
+    SystemReward,
+}
+
+/// EIP-1559 parameters, in force from `ConsensusParams::eip1559_block`
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Eip1559Params {
+    /// base fee of the first EIP-1559 block
+    pub initial_base_fee: U256,
+    pub elasticity_multiplier: u64,
+    pub base_fee_change_denominator: u64,
+    pub base_fee_recipient: BaseFeeRecipient,
+}
+
+impl Eip1559Params {
+    /// BSC geth's parameters for Parlia chains: a base fee of zero, so that only the tip is
+    /// paid, and the fees routed to the system account.
+    pub fn bsc() -> Self {
+        Self {
+            initial_base_fee: U256::zero(),
+            elasticity_multiplier: 2,
+            base_fee_change_denominator: 8,
+            base_fee_recipient: BaseFeeRecipient::SystemReward,
+        }
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Params {
     pub chain_id: ChainId,
     pub network_id: NetworkId,
     #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
     pub additional_forks: BTreeSet<BlockNumber>,
+    #[serde(
+    default,
+    skip_serializing_if = "Option::is_none",
+    with = "::serde_with::rust::unwrap_or_skip"
+    )]
+    pub eip1559: Option<Eip1559Params>,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
@@ -554,6 +634,13 @@ pub enum Seal {
         score: BlockScore,
         signers: Vec<Address>,
         bls_pub_keys: Option<Vec<BLSPublicKey>>,
+        #[serde(default, skip_serializing_if = "H256::is_zero")]
+        mix_hash: H256,
+        #[serde(default, skip_serializing_if = "H64::is_zero")]
+        nonce: H64,
+        /// last 65 bytes of the extraData, zero in a genesis header unless set otherwise
+        #[serde(default, skip_serializing_if = "H520::is_zero")]
+        seal: H520,
     },
     Clique {
         vanity: H256,
@@ -563,11 +650,19 @@ pub enum Seal {
     Unknown
 }
 
+fn is_zero(v: &u64) -> bool {
+    *v == 0
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Genesis {
     pub number: BlockNumber,
+    #[serde(default, skip_serializing_if = "H256::is_zero")]
+    pub parent_hash: H256,
     pub author: Address,
     pub gas_limit: u64,
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub gas_used: u64,
     pub timestamp: u64,
     pub seal: Seal,
     #[serde(
@@ -604,9 +699,23 @@ pub enum Precompile {
     AltBn128Mul { price: u64 },
     AltBn128Pairing { base: u64, pair: u64 },
     Blake2F { gas_per_round: u64 },
+    /// BSC, 0x64: validates a Tendermint header of the Beacon Chain light client
+    TmHeaderValidate { price: u64 },
+    /// BSC, 0x65: validates an IAVL Merkle proof of the Beacon Chain state
+    IavlMerkleProofValidate { price: u64 },
+    /// BSC, 0x66: verifies a BLS signature, `base` plus `per_key` for every aggregated key
+    BlsSignatureVerify { base: u64, per_key: u64 },
+    /// BSC, 0x67: validates a CometBFT light block
+    CometBftLightBlockValidate { price: u64 },
+    /// BSC, 0x68: validates the evidence of a validator signing two headers of one height
+    DoubleSignEvidenceValidate { price: u64 },
+    /// BSC, 0x69: recovers a secp256k1 public key from a Tendermint signature
+    Secp256k1SignatureRecover { price: u64 },
+    /// RIP-7212, 0x100: verifies a secp256r1 signature
+    P256Verify { price: u64 },
 }
 
-#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
 pub struct P2PParams {
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub bootnodes: Vec<String>,
@@ -616,5 +725,11 @@ pub struct P2PParams {
     with = "::serde_with::rust::unwrap_or_skip"
     )]
     pub dns: Option<String>,
+    /// DNS discovery trees besides `dns`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub additional_dns: Vec<String>,
+    /// peers always allowed to connect, even above the peer limit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub trusted_nodes: Vec<String>,
 }
 
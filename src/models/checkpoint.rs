@@ -0,0 +1,21 @@
+use super::{bls::*, chainspec::*};
+use ethereum_types::{Address, H256};
+use serde::{Deserialize, Serialize};
+
+/// Trusted Parlia epoch header Akula can start syncing from instead of genesis,
+/// kept in a companion file next to the chain spec.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Checkpoint {
+    pub number: BlockNumber,
+    pub hash: H256,
+    pub timestamp: u64,
+    /// validator snapshot taken from the epoch header's extraData
+    pub validators: Vec<Address>,
+    #[serde(
+    default,
+    skip_serializing_if = "Option::is_none",
+    with = "::serde_with::rust::unwrap_or_skip"
+    )]
+    pub bls_pub_keys: Option<Vec<BLSPublicKey>>,
+}
@@ -0,0 +1,416 @@
+use super::chainspec::{BlockNumber, ChainId, EXTRA_SEAL_LEN};
+use crate::{crypto::keccak256, util::*};
+use anyhow::{bail, format_err};
+use bytes::Bytes;
+use clap::ArgEnum;
+use ethereum_types::{Address, Bloom, H256, H64, U256};
+use fastrlp::*;
+use serde::{Deserialize, Serialize};
+
+/// BSC block header, in the shape geth returns from `eth_getBlockByNumber`. The optional
+/// fields after the base fee come with Kepler (withdrawals), Tycho (blobs and the beacon
+/// root) and Pascal (requests), each one only present if the ones before it are.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    #[serde(rename = "parentHash")]
+    pub parent_hash: H256,
+    #[serde(rename = "sha3Uncles")]
+    pub ommers_hash: H256,
+    #[serde(rename = "miner")]
+    pub beneficiary: Address,
+    #[serde(rename = "stateRoot")]
+    pub state_root: H256,
+    #[serde(rename = "transactionsRoot")]
+    pub transactions_root: H256,
+    #[serde(rename = "receiptsRoot")]
+    pub receipts_root: H256,
+    #[serde(rename = "logsBloom")]
+    pub logs_bloom: Bloom,
+    pub difficulty: U256,
+    #[serde(with = "hexu64")]
+    pub number: BlockNumber,
+    #[serde(rename = "gasLimit", with = "hexu64")]
+    pub gas_limit: u64,
+    #[serde(rename = "gasUsed", with = "hexu64")]
+    pub gas_used: u64,
+    #[serde(with = "hexu64")]
+    pub timestamp: u64,
+    #[serde(rename = "extraData", with = "hexbytes")]
+    pub extra_data: Bytes,
+    #[serde(rename = "mixHash")]
+    pub mix_hash: H256,
+    pub nonce: H64,
+    #[serde(
+    rename = "baseFeePerGas",
+    default,
+    skip_serializing_if = "Option::is_none"
+    )]
+    pub base_fee_per_gas: Option<U256>,
+    #[serde(rename = "withdrawalsRoot", default, skip_serializing_if = "Option::is_none")]
+    pub withdrawals_root: Option<H256>,
+    #[serde(rename = "blobGasUsed", default, skip_serializing_if = "Option::is_none", with = "hexu64::option")]
+    pub blob_gas_used: Option<u64>,
+    #[serde(rename = "excessBlobGas", default, skip_serializing_if = "Option::is_none", with = "hexu64::option")]
+    pub excess_blob_gas: Option<u64>,
+    #[serde(rename = "parentBeaconBlockRoot", default, skip_serializing_if = "Option::is_none")]
+    pub parent_beacon_block_root: Option<H256>,
+    #[serde(rename = "requestsHash", default, skip_serializing_if = "Option::is_none")]
+    pub requests_hash: Option<H256>,
+}
+
+fn rlp_list(fields: &[&dyn Encodable], out: &mut dyn BufMut) {
+    Header {
+        list: true,
+        payload_length: fields.iter().map(|f| f.length()).sum(),
+    }
+    .encode(out);
+    for field in fields {
+        field.encode(out);
+    }
+}
+
+impl BlockHeader {
+    fn fields(&self) -> Vec<&dyn Encodable> {
+        let mut fields: Vec<&dyn Encodable> = vec![
+            &self.parent_hash,
+            &self.ommers_hash,
+            &self.beneficiary,
+            &self.state_root,
+            &self.transactions_root,
+            &self.receipts_root,
+            &self.logs_bloom,
+            &self.difficulty,
+            &self.number,
+            &self.gas_limit,
+            &self.gas_used,
+            &self.timestamp,
+            &self.extra_data,
+            &self.mix_hash,
+            &self.nonce,
+        ];
+        fields.extend(self.optional_fields());
+        fields
+    }
+
+    /// Trailing fields added by later forks, up to the first one unset.
+    fn optional_fields(&self) -> Vec<&dyn Encodable> {
+        let fields: [Option<&dyn Encodable>; 6] = [
+            self.base_fee_per_gas.as_ref().map(|v| v as &dyn Encodable),
+            self.withdrawals_root.as_ref().map(|v| v as &dyn Encodable),
+            self.blob_gas_used.as_ref().map(|v| v as &dyn Encodable),
+            self.excess_blob_gas.as_ref().map(|v| v as &dyn Encodable),
+            self.parent_beacon_block_root.as_ref().map(|v| v as &dyn Encodable),
+            self.requests_hash.as_ref().map(|v| v as &dyn Encodable),
+        ];
+        fields.into_iter().map_while(|field| field).collect()
+    }
+
+    fn optional_field_count(&self) -> usize {
+        [
+            self.base_fee_per_gas.is_some(),
+            self.withdrawals_root.is_some(),
+            self.blob_gas_used.is_some(),
+            self.excess_blob_gas.is_some(),
+            self.parent_beacon_block_root.is_some(),
+            self.requests_hash.is_some(),
+        ]
+        .into_iter()
+        .filter(|set| *set)
+        .count()
+    }
+
+    /// keccak256 of the RLP encoded header, i.e. the block hash.
+    pub fn hash(&self) -> H256 {
+        let mut out = Vec::with_capacity(self.length());
+        self.encode(&mut out);
+        keccak256(out)
+    }
+
+    /// Hash signed by the Parlia validator: the header with the extraData seal
+    /// stripped, prefixed by the chain id.
+    pub fn parlia_seal_hash(&self, chain_id: ChainId) -> H256 {
+        let extra_len = self.extra_data.len().saturating_sub(EXTRA_SEAL_LEN);
+        let extra_data = &self.extra_data[..extra_len];
+        let mut fields: Vec<&dyn Encodable> = vec![
+            &chain_id,
+            &self.parent_hash,
+            &self.ommers_hash,
+            &self.beneficiary,
+            &self.state_root,
+            &self.transactions_root,
+            &self.receipts_root,
+            &self.logs_bloom,
+            &self.difficulty,
+            &self.number,
+            &self.gas_limit,
+            &self.gas_used,
+            &self.timestamp,
+            &extra_data,
+            &self.mix_hash,
+            &self.nonce,
+        ];
+        // as BSC geth, the Cancun fields are signed once the beacon root is set, to zero
+        if self.parent_beacon_block_root == Some(H256::zero()) {
+            fields.extend(self.optional_fields().into_iter().take(5));
+        }
+        if let Some(requests_hash) = &self.requests_hash {
+            fields.push(requests_hash);
+        }
+        let mut out = Vec::new();
+        rlp_list(&fields, &mut out);
+        keccak256(out)
+    }
+}
+
+impl Encodable for BlockHeader {
+    fn encode(&self, out: &mut dyn BufMut) {
+        rlp_list(&self.fields(), out);
+    }
+
+    fn length(&self) -> usize {
+        let payload_length = self.fields().iter().map(|f| f.length()).sum();
+        length_of_length(payload_length) + payload_length
+    }
+}
+
+impl Decodable for BlockHeader {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let rlp_head = Header::decode(buf)?;
+        if !rlp_head.list {
+            return Err(DecodeError::UnexpectedString);
+        }
+        if buf.len() < rlp_head.payload_length {
+            return Err(DecodeError::InputTooShort);
+        }
+        let leftover = buf.len() - rlp_head.payload_length;
+
+        let mut header = Self {
+            parent_hash: Decodable::decode(buf)?,
+            ommers_hash: Decodable::decode(buf)?,
+            beneficiary: Decodable::decode(buf)?,
+            state_root: Decodable::decode(buf)?,
+            transactions_root: Decodable::decode(buf)?,
+            receipts_root: Decodable::decode(buf)?,
+            logs_bloom: Decodable::decode(buf)?,
+            difficulty: Decodable::decode(buf)?,
+            number: Decodable::decode(buf)?,
+            gas_limit: Decodable::decode(buf)?,
+            gas_used: Decodable::decode(buf)?,
+            timestamp: Decodable::decode(buf)?,
+            extra_data: Decodable::decode(buf)?,
+            mix_hash: Decodable::decode(buf)?,
+            nonce: Decodable::decode(buf)?,
+            base_fee_per_gas: None,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            requests_hash: None,
+        };
+        if buf.len() > leftover {
+            header.base_fee_per_gas = Some(Decodable::decode(buf)?);
+        }
+        if buf.len() > leftover {
+            header.withdrawals_root = Some(Decodable::decode(buf)?);
+        }
+        if buf.len() > leftover {
+            header.blob_gas_used = Some(Decodable::decode(buf)?);
+        }
+        if buf.len() > leftover {
+            header.excess_blob_gas = Some(Decodable::decode(buf)?);
+        }
+        if buf.len() > leftover {
+            header.parent_beacon_block_root = Some(Decodable::decode(buf)?);
+        }
+        if buf.len() > leftover {
+            header.requests_hash = Some(Decodable::decode(buf)?);
+        }
+        if buf.len() != leftover {
+            return Err(DecodeError::ListLengthMismatch {
+                expected: rlp_head.payload_length,
+                got: rlp_head.payload_length + leftover - buf.len(),
+            });
+        }
+
+        Ok(header)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ArgEnum)]
+pub enum HeaderFormat {
+    Json,
+    Rlp,
+}
+
+/// parse_headers reads headers either as geth RPC JSON (objects, JSON-RPC responses or arrays,
+/// whitespace separated) or as RLP (raw, or hex encoded one per line). The format is detected
+/// from the content when not given. Returns every header with the hash reported in its JSON, if any.
+pub fn parse_headers(
+    raw: &[u8],
+    format: Option<HeaderFormat>,
+) -> anyhow::Result<Vec<(BlockHeader, Option<H256>)>> {
+    let text = std::str::from_utf8(raw).ok();
+    let format = format.unwrap_or(match text {
+        Some(text) if text.trim_start().starts_with(['{', '[']) => HeaderFormat::Json,
+        _ => HeaderFormat::Rlp,
+    });
+
+    let mut headers = Vec::new();
+    match (format, text) {
+        (HeaderFormat::Json, _) => {
+            for value in serde_json::Deserializer::from_slice(raw).into_iter::<serde_json::Value>() {
+                let mut value = value?;
+                if let Some(result) = value.get_mut("result") {
+                    value = result.take();
+                }
+                let values = match value {
+                    serde_json::Value::Array(values) => values,
+                    value => vec![value],
+                };
+                for value in values {
+                    let hash = match value.get("hash") {
+                        Some(hash) => Some(serde_json::from_value(hash.clone())?),
+                        None => None,
+                    };
+                    let header: BlockHeader = serde_json::from_value(value)?;
+                    if header.optional_fields().len() != header.optional_field_count() {
+                        bail!("header {} has a field of a later fork without the ones before it", header.number);
+                    }
+                    headers.push((header, hash));
+                }
+            }
+        }
+        (HeaderFormat::Rlp, Some(text)) => {
+            for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                let bytes = hex::decode(line.strip_prefix("0x").unwrap_or(line))?;
+                let header = BlockHeader::decode(&mut &*bytes)
+                    .map_err(|e| format_err!("invalid header rlp: {}", e))?;
+                headers.push((header, None));
+            }
+        }
+        (HeaderFormat::Rlp, None) => {
+            let mut buf = raw;
+            while !buf.is_empty() {
+                let header = BlockHeader::decode(&mut buf)
+                    .map_err(|e| format_err!("invalid header rlp: {}", e))?;
+                headers.push((header, None));
+            }
+        }
+    }
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    fn header() -> BlockHeader {
+        BlockHeader {
+            parent_hash: H256::repeat_byte(0x11),
+            ommers_hash: H256(hex!("1dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347")),
+            beneficiary: Address::repeat_byte(0x22),
+            state_root: H256::repeat_byte(0x33),
+            transactions_root: H256::repeat_byte(0x44),
+            receipts_root: H256::repeat_byte(0x55),
+            logs_bloom: Bloom::zero(),
+            difficulty: U256::from(2),
+            number: BlockNumber(200),
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            timestamp: 1_650_000_000,
+            extra_data: vec![0u8; 32 + 65].into(),
+            mix_hash: H256::zero(),
+            nonce: H64::zero(),
+            base_fee_per_gas: None,
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
+            requests_hash: None,
+        }
+    }
+
+    /// Header after Pascal, carrying every optional field.
+    fn pascal_header() -> BlockHeader {
+        BlockHeader {
+            base_fee_per_gas: Some(U256::zero()),
+            withdrawals_root: Some(H256(hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"))),
+            blob_gas_used: Some(0),
+            excess_blob_gas: Some(0),
+            parent_beacon_block_root: Some(H256::zero()),
+            requests_hash: Some(H256::repeat_byte(0x66)),
+            ..header()
+        }
+    }
+
+    #[test]
+    fn header_rlp_roundtrip() {
+        let pascal = pascal_header();
+        let london = BlockHeader { base_fee_per_gas: Some(U256::zero()), ..header() };
+        let kepler = BlockHeader { withdrawals_root: pascal.withdrawals_root, ..london.clone() };
+        let tycho = BlockHeader { requests_hash: None, ..pascal.clone() };
+        for header in [header(), london, kepler, tycho, pascal] {
+            let mut out = Vec::new();
+            header.encode(&mut out);
+            assert_eq!(out.len(), header.length());
+            assert_eq!(header, BlockHeader::decode(&mut &*out).unwrap());
+        }
+    }
+
+    #[test]
+    fn seal_hash_of_later_forks() {
+        // BSC geth signs the Cancun fields with a zero beacon root, and the requests hash
+        let pascal = pascal_header();
+        let tycho = BlockHeader { requests_hash: None, ..pascal.clone() };
+        let london = BlockHeader { base_fee_per_gas: Some(U256::zero()), ..header() };
+        let seal_hash = |header: &BlockHeader| header.parlia_seal_hash(ChainId(56));
+        assert_eq!(seal_hash(&header()), seal_hash(&london));
+        assert_ne!(seal_hash(&london), seal_hash(&tycho));
+        assert_ne!(seal_hash(&tycho), seal_hash(&pascal));
+        let beacon = BlockHeader { parent_beacon_block_root: Some(H256::repeat_byte(1)), requests_hash: None, ..pascal };
+        assert_eq!(seal_hash(&header()), seal_hash(&beacon));
+    }
+
+    #[test]
+    fn header_json_roundtrip() {
+        let header = header();
+        let json = serde_json::to_string(&header).unwrap();
+        assert!(json.contains(r#""number":"0xc8""#));
+        assert!(!json.contains("withdrawalsRoot"));
+        assert_eq!(header, serde_json::from_str(&json).unwrap());
+
+        let header = pascal_header();
+        let json = serde_json::to_string(&header).unwrap();
+        assert!(json.contains(r#""blobGasUsed":"0x0""#));
+        assert!(json.contains(r#""parentBeaconBlockRoot":"0x0000"#));
+        assert_eq!(header, serde_json::from_str(&json).unwrap());
+        assert_eq!(header, parse_headers(json.as_bytes(), None).unwrap()[0].0);
+
+        // a field of a later fork without the ones before it has no RLP form
+        let header = BlockHeader { withdrawals_root: None, ..header };
+        let json = serde_json::to_string(&header).unwrap();
+        let err = parse_headers(json.as_bytes(), None).unwrap_err();
+        assert_eq!("header 200 has a field of a later fork without the ones before it", err.to_string());
+    }
+
+    #[test]
+    fn parse_headers_formats() {
+        let header = header();
+        let json = serde_json::to_string(&header).unwrap();
+        let mut rlp = Vec::new();
+        header.encode(&mut rlp);
+
+        for raw in [
+            format!(r#"{{"jsonrpc":"2.0","id":1,"result":{}}}"#, json),
+            format!("[{},{}]", json, json),
+            format!("{}\n{}\n", json, json),
+            format!("0x{}\n{}", hex::encode(&rlp), hex::encode(&rlp)),
+        ] {
+            let headers = parse_headers(raw.as_bytes(), None).unwrap();
+            assert!(headers.iter().all(|(h, hash)| *h == header && hash.is_none()));
+        }
+        let raw = [rlp.clone(), rlp].concat();
+        assert_eq!(2, parse_headers(&raw, Some(HeaderFormat::Rlp)).unwrap().len());
+    }
+}
@@ -1,3 +1,6 @@
 pub mod bsc;
 pub mod chainspec;
-pub mod bls;
\ No newline at end of file
+pub mod bls;
+pub mod checkpoint;
+pub mod header;
+pub mod vote;
\ No newline at end of file
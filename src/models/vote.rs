@@ -0,0 +1,151 @@
+use super::bls::*;
+use crate::crypto::*;
+use bytes::Bytes;
+use ethereum_types::{Address, H256};
+use fastrlp::*;
+use serde::{Deserialize, Serialize};
+
+/// Bitset of the validators, sorted by address, that signed a vote attestation.
+pub type ValidatorsBitSet = u64;
+
+/// Maximum number of validators an attestation bitset can refer to
+pub const MAX_ATTESTATION_VALIDATORS: usize = ValidatorsBitSet::BITS as usize;
+
+/// Maximum length of the extra field of an attestation, as geth enforces
+pub const MAX_ATTESTATION_EXTRA_LEN: usize = 256;
+
+/// VoteData is what validators sign under fast finality: the justified block the vote
+/// builds on and the block it votes for.
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, RlpEncodable, RlpDecodable,
+)]
+pub struct VoteData {
+    pub source_number: u64,
+    pub source_hash: H256,
+    pub target_number: u64,
+    pub target_hash: H256,
+}
+
+impl VoteData {
+    /// Hash of the RLP of the vote, the message of its BLS signatures.
+    pub fn hash(&self) -> H256 {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        keccak256(out)
+    }
+}
+
+/// VoteEnvelope is a single validator vote, as gossiped between validators.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, RlpEncodable, RlpDecodable)]
+pub struct VoteEnvelope {
+    pub vote_address: BLSPublicKey,
+    pub signature: BLSSignature,
+    pub data: VoteData,
+}
+
+impl VoteEnvelope {
+    pub fn hash(&self) -> H256 {
+        let mut out = Vec::new();
+        self.encode(&mut out);
+        keccak256(out)
+    }
+
+    pub fn verify(&self) -> bool {
+        bls_verify(&self.vote_address, self.data.hash().as_bytes(), &self.signature).is_ok()
+    }
+}
+
+/// VoteAttestation aggregates the votes of the validators for a block, it is carried in
+/// the extraData of the block's child.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize, RlpEncodable, RlpDecodable)]
+pub struct VoteAttestation {
+    pub vote_address_set: ValidatorsBitSet,
+    pub agg_signature: BLSSignature,
+    pub data: VoteData,
+    #[serde(with = "crate::util::hexbytes")]
+    pub extra: Bytes,
+}
+
+impl VoteAttestation {
+    /// Indexes of the validators that voted, into the validators sorted by address.
+    pub fn voter_indexes(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..MAX_ATTESTATION_VALIDATORS).filter(move |i| self.vote_address_set & (1 << i) != 0)
+    }
+
+    /// Validators that voted, None if the bitset refers to more validators than there are.
+    pub fn voters(&self, validators: &[Address]) -> Option<Vec<Address>> {
+        self.voter_indexes().map(|i| validators.get(i).copied()).collect()
+    }
+
+    /// verify checks the aggregated signature against the BLS keys of the validators, sorted
+    /// by address, that the bitset selects.
+    pub fn verify(&self, vote_addrs: &[BLSPublicKey]) -> bool {
+        let keys: Option<Vec<BLSPublicKey>> =
+            self.voter_indexes().map(|i| vote_addrs.get(i).copied()).collect();
+        match keys {
+            Some(keys) => {
+                bls_fast_aggregate_verify(&keys, self.data.hash().as_bytes(), &self.agg_signature).is_ok()
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blst::min_pk::SecretKey;
+
+    fn data() -> VoteData {
+        VoteData {
+            source_number: 10,
+            source_hash: H256::repeat_byte(0x0a),
+            target_number: 11,
+            target_hash: H256::repeat_byte(0x0b),
+        }
+    }
+
+    #[test]
+    fn vote_rlp_roundtrip() {
+        let attestation = VoteAttestation {
+            vote_address_set: 0b1011,
+            agg_signature: BLSSignature::repeat_byte(0x11),
+            data: data(),
+            extra: Bytes::from_static(b"extra"),
+        };
+        let mut out = Vec::new();
+        attestation.encode(&mut out);
+        assert_eq!(attestation, VoteAttestation::decode(&mut &out[..]).unwrap());
+        assert_eq!(vec![0, 1, 3], attestation.voter_indexes().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn verify_aggregated_votes() {
+        let secrets: Vec<SecretKey> =
+            (1..=4u8).map(|i| SecretKey::key_gen(&[i; 32], &[]).unwrap()).collect();
+        let keys: Vec<BLSPublicKey> = secrets.iter().map(bls_public).collect();
+        let data = data();
+
+        let vote = VoteEnvelope {
+            vote_address: keys[0],
+            signature: bls_sign(&secrets[0], data.hash().as_bytes()),
+            data,
+        };
+        assert!(vote.verify());
+
+        let sigs: Vec<BLSSignature> =
+            [0, 2, 3].iter().map(|i| bls_sign(&secrets[*i], data.hash().as_bytes())).collect();
+        let mut attestation = VoteAttestation {
+            vote_address_set: 0b1101,
+            agg_signature: bls_aggregate(&sigs).unwrap(),
+            data,
+            extra: Bytes::new(),
+        };
+        assert!(attestation.verify(&keys));
+
+        attestation.vote_address_set = 0b0111;
+        assert!(!attestation.verify(&keys));
+        attestation.vote_address_set = 0b1_0000_1101;
+        assert!(!attestation.verify(&keys));
+    }
+}
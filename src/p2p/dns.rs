@@ -0,0 +1,377 @@
+use super::{Enr, NodeRecordError};
+use crate::crypto::*;
+use secp256k1::{ecdsa::Signature, Message, PublicKey, SecretKey, SECP256K1};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+
+const ROOT_PREFIX: &str = "enrtree-root:v1";
+const BRANCH_PREFIX: &str = "enrtree-branch:";
+const LINK_PREFIX: &str = "enrtree://";
+
+/// Maximum number of children of a branch entry, for it to fit a TXT record as in geth
+pub const MAX_CHILDREN: usize = 13;
+/// TTL of the root record, which changes on every update of the tree
+pub const ROOT_TTL: u32 = 30 * 60;
+/// TTL of the other records, which never change as they are named by their hash
+pub const ENTRY_TTL: u32 = 4 * 7 * 24 * 60 * 60;
+
+const BASE32_ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Debug)]
+pub enum DnsTreeError {
+    InvalidUrl(String),
+    InvalidRoot(String),
+    WrongRootSignature,
+    InvalidZoneLine { line: usize },
+    MissingEntry(String),
+    WrongEntryHash { subdomain: String, hash: String },
+    InvalidEntry(String),
+    InvalidRecord { subdomain: String, err: NodeRecordError },
+}
+
+impl std::fmt::Display for DnsTreeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for DnsTreeError {}
+
+/// Base32 (RFC 4648) without padding
+fn base32_encode(data: &[u8]) -> String {
+    let mut out = String::new();
+    for chunk in data.chunks(5) {
+        let mut buf = [0u8; 5];
+        buf[..chunk.len()].copy_from_slice(chunk);
+        let bits = u64::from_be_bytes([0, 0, 0, buf[0], buf[1], buf[2], buf[3], buf[4]]);
+        for i in 0..(chunk.len() * 8 + 4) / 5 {
+            out.push(BASE32_ALPHABET[(bits >> (35 - i * 5)) as usize & 31] as char);
+        }
+    }
+    out
+}
+
+fn base32_decode(s: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut bits, mut len) = (0u64, 0);
+    for c in s.trim_end_matches('=').bytes() {
+        let v = BASE32_ALPHABET.iter().position(|a| *a == c.to_ascii_uppercase())?;
+        bits = (bits << 5) | v as u64;
+        len += 5;
+        if len >= 8 {
+            len -= 8;
+            out.push((bits >> len) as u8);
+        }
+    }
+    Some(out)
+}
+
+/// Subdomain an entry is published under: the base32 of the first 16 bytes of its keccak256 hash.
+pub fn subdomain(entry: &str) -> String {
+    base32_encode(&keccak256(entry)[..16])
+}
+
+/// `enrtree://<key>@<domain>` URL of a tree, the key being the compressed public key the
+/// root is signed with.
+pub fn tree_url(public_key: &PublicKey, domain: &str) -> String {
+    format!("{}{}@{}", LINK_PREFIX, base32_encode(&public_key.serialize()), domain)
+}
+
+/// Public key and domain of an `enrtree://` URL
+pub fn parse_tree_url(url: &str) -> Result<(PublicKey, String), DnsTreeError> {
+    let invalid = || DnsTreeError::InvalidUrl(url.to_string());
+    let (key, domain) = url.strip_prefix(LINK_PREFIX).and_then(|s| s.split_once('@')).ok_or_else(invalid)?;
+    let key = base32_decode(key).ok_or_else(invalid)?;
+    let public_key = PublicKey::from_slice(&key).map_err(|_| invalid())?;
+    if domain.is_empty() {
+        return Err(invalid());
+    }
+    Ok((public_key, domain.trim_end_matches('.').to_lowercase()))
+}
+
+/// DnsTree is an EIP-1459 tree of node records and links to other trees, as the TXT records
+/// of its entries by subdomain and a root pointing at the record and link subtrees.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DnsTree {
+    pub seq: u64,
+    pub enr_root: String,
+    pub link_root: String,
+    /// base64 of the 65 bytes root signature, empty until signed
+    pub signature: String,
+    pub entries: BTreeMap<String, String>,
+}
+
+impl DnsTree {
+    /// build lays out the tree of the records, sorted by node ID as in geth, and of the links.
+    pub fn build(records: &[Enr], links: &[String], seq: u64) -> Self {
+        let mut records: Vec<&Enr> = records.iter().collect();
+        records.sort_by_key(|enr| enr.node_id());
+        records.dedup_by_key(|enr| enr.node_id());
+        let mut links: Vec<String> = links.to_vec();
+        links.dedup();
+
+        let mut entries = BTreeMap::new();
+        let enr_root = Self::build_subtree(&mut entries, records.iter().map(|enr| enr.to_string()).collect());
+        let link_root = Self::build_subtree(&mut entries, links);
+        Self { seq, enr_root, link_root, signature: String::new(), entries }
+    }
+
+    /// Adds the entries of a subtree, returning the subdomain of its root.
+    fn build_subtree(entries: &mut BTreeMap<String, String>, mut leaves: Vec<String>) -> String {
+        while leaves.len() > MAX_CHILDREN {
+            leaves = leaves
+                .chunks(MAX_CHILDREN)
+                .map(|chunk| Self::branch(entries, chunk))
+                .collect();
+        }
+        let root = if leaves.len() == 1 { leaves.remove(0) } else { Self::branch(entries, &leaves) };
+        let hash = subdomain(&root);
+        entries.insert(hash.clone(), root);
+        hash
+    }
+
+    fn branch(entries: &mut BTreeMap<String, String>, children: &[String]) -> String {
+        if children.len() == 1 {
+            return children[0].clone();
+        }
+        let hashes: Vec<String> = children
+            .iter()
+            .map(|child| {
+                let hash = subdomain(child);
+                entries.insert(hash.clone(), child.clone());
+                hash
+            })
+            .collect();
+        format!("{}{}", BRANCH_PREFIX, hashes.join(","))
+    }
+
+    fn signed_content(&self) -> String {
+        format!("{} e={} l={} seq={}", ROOT_PREFIX, self.enr_root, self.link_root, self.seq)
+    }
+
+    pub fn sign(&mut self, key: &SecretKey) -> Result<(), secp256k1::Error> {
+        let sig = sign(keccak256(self.signed_content()), key)?;
+        self.signature = base64::encode_config(sig, base64::URL_SAFE_NO_PAD);
+        Ok(())
+    }
+
+    /// Root record of the tree, published at the domain itself
+    pub fn root(&self) -> String {
+        format!("{} sig={}", self.signed_content(), self.signature)
+    }
+
+    /// to_zone writes the tree as a DNS zone file of TXT records, splitting the ones longer
+    /// than the 255 characters a TXT string may hold.
+    pub fn to_zone(&self, domain: &str) -> String {
+        let txt = |s: &str| {
+            let chunks: Vec<String> =
+                s.as_bytes().chunks(255).map(|c| format!("\"{}\"", String::from_utf8_lossy(c))).collect();
+            chunks.join(" ")
+        };
+        let mut zone = format!("$ORIGIN {}.\n", domain.trim_end_matches('.'));
+        zone.push_str(&format!("@ {} IN TXT {}\n", ROOT_TTL, txt(&self.root())));
+        for (subdomain, entry) in &self.entries {
+            zone.push_str(&format!("{} {} IN TXT {}\n", subdomain, ENTRY_TTL, txt(entry)));
+        }
+        zone
+    }
+}
+
+/// Content of a TXT record, its quoted strings concatenated, or else the rest of the line.
+fn parse_txt(s: &str) -> Option<String> {
+    let s = s.trim();
+    if !s.starts_with('"') {
+        return Some(s.to_string());
+    }
+    let mut out = String::new();
+    let mut rest = s;
+    while let Some(quoted) = rest.strip_prefix('"') {
+        let end = quoted.find('"')?;
+        out.push_str(&quoted[..end]);
+        rest = quoted[end + 1..].trim_start();
+    }
+    if rest.is_empty() || rest.starts_with(';') {
+        Some(out)
+    } else {
+        None
+    }
+}
+
+/// parse_zone reads the TXT records of a zone file by their lowercase fully qualified name,
+/// as written by `to_zone` or with the TTL, class and quotes left out.
+pub fn parse_zone(zone: &str) -> Result<BTreeMap<String, String>, DnsTreeError> {
+    let mut origin = String::new();
+    let mut records = BTreeMap::new();
+    for (i, line) in zone.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        let invalid = || DnsTreeError::InvalidZoneLine { line: i + 1 };
+        let mut tokens = line.splitn(2, char::is_whitespace);
+        let name = tokens.next().ok_or_else(invalid)?;
+        let mut rest = tokens.next().unwrap_or("").trim_start();
+        if name.eq_ignore_ascii_case("$ORIGIN") {
+            origin = rest.trim().trim_end_matches('.').to_lowercase();
+            continue;
+        }
+        if name.starts_with('$') {
+            continue;
+        }
+        // skip the TTL and class
+        loop {
+            let (token, tail) = rest.split_once(char::is_whitespace).ok_or_else(invalid)?;
+            rest = tail.trim_start();
+            if token.eq_ignore_ascii_case("TXT") {
+                break;
+            }
+            if !(token.chars().all(|c| c.is_ascii_digit()) || token.eq_ignore_ascii_case("IN")) {
+                return Err(invalid());
+            }
+        }
+        let name = match name {
+            "@" => origin.clone(),
+            name if name.ends_with('.') => name.trim_end_matches('.').to_lowercase(),
+            name if origin.is_empty() => name.to_lowercase(),
+            name => format!("{}.{}", name.to_lowercase(), origin),
+        };
+        records.insert(name, parse_txt(rest).ok_or_else(invalid)?);
+    }
+    Ok(records)
+}
+
+/// Tree as read and checked from a zone file
+#[derive(Clone, Debug)]
+pub struct VerifiedTree {
+    pub seq: u64,
+    pub records: Vec<Enr>,
+    pub links: Vec<String>,
+}
+
+/// verify_zone checks the tree of a zone file against its `enrtree://` URL: the root must be
+/// signed by the URL's key, every entry reachable from it must be published under its hash,
+/// and the record subtree must hold valid node records, the link one links only.
+pub fn verify_zone(url: &str, zone: &str) -> Result<VerifiedTree, DnsTreeError> {
+    let (public_key, domain) = parse_tree_url(url)?;
+    let txt = parse_zone(zone)?;
+
+    let root = txt.get(&domain).ok_or_else(|| DnsTreeError::MissingEntry(domain.clone()))?;
+    let invalid_root = || DnsTreeError::InvalidRoot(root.clone());
+    let fields: BTreeMap<&str, &str> = root
+        .strip_prefix(ROOT_PREFIX)
+        .ok_or_else(invalid_root)?
+        .split_whitespace()
+        .map(|field| field.split_once('=').ok_or_else(invalid_root))
+        .collect::<Result<_, _>>()?;
+    let field = |key: &str| fields.get(key).copied().ok_or_else(invalid_root);
+    let tree = DnsTree {
+        seq: field("seq")?.parse().map_err(|_| invalid_root())?,
+        enr_root: field("e")?.to_string(),
+        link_root: field("l")?.to_string(),
+        signature: field("sig")?.to_string(),
+        entries: BTreeMap::new(),
+    };
+    let sig = base64::decode_config(&tree.signature, base64::URL_SAFE_NO_PAD).map_err(|_| invalid_root())?;
+    if sig.len() != 65 {
+        return Err(DnsTreeError::WrongRootSignature);
+    }
+    let sig = Signature::from_compact(&sig[..64]).map_err(|_| DnsTreeError::WrongRootSignature)?;
+    let message = Message::from_slice(keccak256(tree.signed_content()).as_bytes()).expect("hash is 32 bytes");
+    SECP256K1
+        .verify_ecdsa(&message, &sig, &public_key)
+        .map_err(|_| DnsTreeError::WrongRootSignature)?;
+
+    let mut verified = VerifiedTree { seq: tree.seq, records: Vec::new(), links: Vec::new() };
+    let mut visited = BTreeSet::new();
+    for (root, is_link_tree) in [(&tree.enr_root, false), (&tree.link_root, true)] {
+        let mut queue = VecDeque::from([root.clone()]);
+        while let Some(hash) = queue.pop_front() {
+            if !visited.insert((hash.clone(), is_link_tree)) {
+                continue;
+            }
+            let name = format!("{}.{}", hash.to_lowercase(), domain);
+            let entry = txt.get(&name).ok_or_else(|| DnsTreeError::MissingEntry(name.clone()))?;
+            if subdomain(entry) != hash.to_uppercase() {
+                return Err(DnsTreeError::WrongEntryHash { subdomain: hash, hash: subdomain(entry) });
+            }
+            if let Some(children) = entry.strip_prefix(BRANCH_PREFIX) {
+                queue.extend(children.split(',').filter(|child| !child.is_empty()).map(String::from));
+            } else if entry.starts_with(LINK_PREFIX) && is_link_tree {
+                parse_tree_url(entry)?;
+                verified.links.push(entry.clone());
+            } else if entry.starts_with("enr:") && !is_link_tree {
+                let enr = entry.parse().map_err(|err| DnsTreeError::InvalidRecord { subdomain: hash, err })?;
+                verified.records.push(enr);
+            } else {
+                return Err(DnsTreeError::InvalidEntry(entry.clone()));
+            }
+        }
+    }
+    Ok(verified)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::{IpAddr, Ipv4Addr};
+
+    // example tree of EIP-1459, its root is signed by the key the geth tests use rather than
+    // the one of the example URL
+    const URL: &str = "enrtree://AKPYQIUQIL7PSIACI32J7FGZW56E5FKHEFCCOFHILBIMW3M6LWXS2@nodes.example.org";
+    const ZONE: &str = r#"; name                        ttl     class type  content
+@                             60      IN    TXT   enrtree-root:v1 e=JWXYDBPXYWG6FX3GMDIBFA6CJ4 l=C7HRFPF3BLGF3YR4DY5KX3SMBE seq=1 sig=o908WmNp7LibOfPsr4btQwatZJ5URBr2ZAuxvK4UWHlsB9sUOTJQaGAlLPVAhM__XJesCHxLISo94z5Z2a463gA
+C7HRFPF3BLGF3YR4DY5KX3SMBE    86900   IN    TXT   enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHWZG3YYHIUV7BVDQ5FDPRT2@morenodes.example.org
+JWXYDBPXYWG6FX3GMDIBFA6CJ4    86900   IN    TXT   enrtree-branch:2XS2367YHAXJFGLZHVAWLQD4ZY,H4FHT4B454P6UXFD7JCYQ5PWDY,MHTDO6TMUBRIA2XWG5LUDACK24
+2XS2367YHAXJFGLZHVAWLQD4ZY    86900   IN    TXT   enr:-HW4QOFzoVLaFJnNhbgMoDXPnOvcdVuj7pDpqRvh6BRDO68aVi5ZcjB3vzQRZH2IcLBGHzo8uUN3snqmgTiE56CH3AMBgmlkgnY0iXNlY3AyNTZrMaECC2_24YYkYHEgdzxlSNKQEnHhuNAbNlMlWJxrJxbAFvA
+H4FHT4B454P6UXFD7JCYQ5PWDY    86900   IN    TXT   enr:-HW4QAggRauloj2SDLtIHN1XBkvhFZ1vtf1raYQp9TBW2RD5EEawDzbtSmlXUfnaHcvwOizhVYLtr7e6vw7NAf6mTuoCgmlkgnY0iXNlY3AyNTZrMaECjrXI8TLNXU0f8cthpAMxEshUyQlK-AM0PW2wfrnacNI
+MHTDO6TMUBRIA2XWG5LUDACK24    86900   IN    TXT   enr:-HW4QLAYqmrwllBEnzWWs7I5Ev2IAs7x_dZlbYdRdMUx5EyKHDXp7AV5CkuPGUPdvbv1_Ms1CPfhcGCvSElSosZmyoqAgmlkgnY0iXNlY3AyNTZrMaECriawHKWdDRk2xeZkrOXBQ0dfMFLHY4eENZwdufn1S1o
+"#;
+
+    #[test]
+    fn verify_example_tree() {
+        let zone = ZONE.replace("\n@", "\n$ORIGIN nodes.example.org.\n@");
+        let tree = verify_zone(URL, &zone).unwrap();
+        assert_eq!(1, tree.seq);
+        assert_eq!(3, tree.records.len());
+        assert_eq!(vec!["enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHWZG3YYHIUV7BVDQ5FDPRT2@morenodes.example.org"], tree.links);
+
+        let built = DnsTree::build(&tree.records, &tree.links, 1);
+        assert_eq!("JWXYDBPXYWG6FX3GMDIBFA6CJ4", built.enr_root);
+        assert_eq!("C7HRFPF3BLGF3YR4DY5KX3SMBE", built.link_root);
+
+        let tampered = zone.replace("seq=1", "seq=2");
+        assert!(matches!(verify_zone(URL, &tampered), Err(DnsTreeError::WrongRootSignature)));
+        let url = "enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHWZG3YYHIUV7BVDQ5FDPRT2@nodes.example.org";
+        assert!(matches!(verify_zone(url, &zone), Err(DnsTreeError::WrongRootSignature)));
+    }
+
+    #[test]
+    fn build_and_verify_tree() {
+        let ip = Some(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let records: Vec<Enr> = (1..=30u8)
+            .map(|i| Enr::build(&SecretKey::from_slice(&[i; 32]).unwrap(), 1, ip, Some(30300 + i as u16), None))
+            .collect();
+        let key = SecretKey::from_slice(&[0x42; 32]).unwrap();
+        let url = tree_url(&PublicKey::from_secret_key(SECP256K1, &key), "nodes.devnet.local");
+        let (public_key, domain) = parse_tree_url(&url).unwrap();
+        assert_eq!(PublicKey::from_secret_key(SECP256K1, &key), public_key);
+        assert_eq!("nodes.devnet.local", domain);
+
+        let mut tree = DnsTree::build(&records, &[], 7);
+        tree.sign(&key).unwrap();
+        let zone = tree.to_zone("nodes.devnet.local");
+        let verified = verify_zone(&url, &zone).unwrap();
+        assert_eq!(7, verified.seq);
+        let mut ids: Vec<_> = verified.records.iter().map(|enr| enr.node_id()).collect();
+        ids.sort();
+        let mut expected: Vec<_> = records.iter().map(|enr| enr.node_id()).collect();
+        expected.sort();
+        assert_eq!(expected, ids);
+        assert!(verified.links.is_empty());
+
+        // drop a leaf
+        let leaf = subdomain(&records[0].to_string());
+        let zone: Vec<&str> = zone.lines().filter(|line| !line.starts_with(&leaf)).collect();
+        let zone = zone.join("\n");
+        assert!(matches!(verify_zone(&url, &zone), Err(DnsTreeError::MissingEntry(_))));
+    }
+}
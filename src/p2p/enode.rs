@@ -0,0 +1,113 @@
+use super::NodeRecordError;
+use ethereum_types::H512;
+use secp256k1::PublicKey;
+use std::{fmt, net::IpAddr, str::FromStr};
+
+/// Enode is a node as given by an `enode://<id>@<ip>:<port>?discport=<port>` URL, its ID
+/// being its uncompressed public key without the prefix byte.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Enode {
+    pub id: H512,
+    pub ip: IpAddr,
+    pub tcp_port: u16,
+    /// discovery port, the TCP port unless `discport` says otherwise
+    pub udp_port: u16,
+}
+
+impl Enode {
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey::from_slice(&[&[4][..], self.id.as_bytes()].concat()).expect("checked on parsing")
+    }
+}
+
+fn parse_port(s: &str) -> Result<u16, ()> {
+    match s.parse() {
+        Ok(0) | Err(_) => Err(()),
+        Ok(port) => Ok(port),
+    }
+}
+
+impl FromStr for Enode {
+    type Err = NodeRecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.strip_prefix("enode://").ok_or(NodeRecordError::UnknownScheme)?;
+        let (id, addr) = s.split_once('@').unwrap_or((s, ""));
+        let id = hex::decode(id).map_err(NodeRecordError::InvalidNodeId)?;
+        if id.len() != 64 {
+            return Err(NodeRecordError::WrongNodeIdLen { len: id.len() });
+        }
+        PublicKey::from_slice(&[&[4][..], &id[..]].concat()).map_err(|_| NodeRecordError::InvalidPublicKey)?;
+
+        let (addr, query) = addr.split_once('?').unwrap_or((addr, ""));
+        let (ip, port) = addr.rsplit_once(':').ok_or_else(|| NodeRecordError::InvalidPort(addr.to_string()))?;
+        let ip = ip
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .parse()
+            .map_err(|_| NodeRecordError::InvalidIp(ip.to_string()))?;
+        let tcp_port = parse_port(port).map_err(|_| NodeRecordError::InvalidPort(port.to_string()))?;
+
+        let mut udp_port = tcp_port;
+        for (key, value) in query.split('&').filter_map(|param| param.split_once('=')) {
+            if key == "discport" {
+                udp_port = parse_port(value).map_err(|_| NodeRecordError::InvalidDiscPort(value.to_string()))?;
+            }
+        }
+        Ok(Self { id: H512::from_slice(&id), ip, tcp_port, udp_port })
+    }
+}
+
+impl fmt::Display for Enode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "enode://{}@", hex::encode(self.id))?;
+        match self.ip {
+            IpAddr::V4(ip) => write!(f, "{}", ip)?,
+            IpAddr::V6(ip) => write!(f, "[{}]", ip)?,
+        }
+        write!(f, ":{}", self.tcp_port)?;
+        if self.udp_port != self.tcp_port {
+            write!(f, "?discport={}", self.udp_port)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ID: &str = "79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8";
+
+    #[test]
+    fn parse_enode() {
+        let s = format!("enode://{}@10.0.0.1:30311?discport=30301", ID);
+        let enode: Enode = s.parse().unwrap();
+        assert_eq!("10.0.0.1".parse::<IpAddr>().unwrap(), enode.ip);
+        assert_eq!((30311, 30301), (enode.tcp_port, enode.udp_port));
+        assert_eq!(s, enode.to_string());
+        assert_eq!(enode.id.as_bytes(), &enode.public_key().serialize_uncompressed()[1..]);
+
+        let enode: Enode = format!("enode://{}@[::1]:30311", ID).parse().unwrap();
+        assert_eq!(format!("enode://{}@[::1]:30311", ID), enode.to_string());
+
+        let err = |s: String| s.parse::<Enode>().unwrap_err();
+        assert!(matches!(err(format!("enr://{}@10.0.0.1:30311", ID)), NodeRecordError::UnknownScheme));
+        assert!(matches!(
+            err(format!("enode://{}00@10.0.0.1:30311", ID)),
+            NodeRecordError::WrongNodeIdLen { len: 65 }
+        ));
+        // x coordinate of the generator with a wrong y
+        assert!(matches!(
+            err(format!("enode://{}{}@10.0.0.1:30311", &ID[..64], "00".repeat(32))),
+            NodeRecordError::InvalidPublicKey
+        ));
+        assert!(matches!(err(format!("enode://{}@10.0.0:30311", ID)), NodeRecordError::InvalidIp(_)));
+        assert!(matches!(err(format!("enode://{}@10.0.0.1:70000", ID)), NodeRecordError::InvalidPort(_)));
+        assert!(matches!(err(format!("enode://{}@10.0.0.1", ID)), NodeRecordError::InvalidPort(_)));
+        assert!(matches!(
+            err(format!("enode://{}@10.0.0.1:30311?discport=x", ID)),
+            NodeRecordError::InvalidDiscPort(_)
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+use super::{Enode, NodeRecordError};
+use crate::crypto::keccak256;
+use bytes::{Buf, Bytes};
+use ethereum_types::{H256, H512};
+use fastrlp::{Decodable, Encodable, Header};
+use secp256k1::{ecdsa::Signature, Message, PublicKey, SecretKey, SECP256K1};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    str::FromStr,
+};
+
+/// Maximum size of an encoded node record
+pub const MAX_RECORD_SIZE: usize = 300;
+
+/// Enr is an Ethereum node record (EIP-778), as given by its `enr:` text form, signed under
+/// the "v4" identity scheme.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Enr {
+    pub seq: u64,
+    pub public_key: PublicKey,
+    /// every key/value pair of the record, values still RLP encoded
+    pub pairs: BTreeMap<String, Bytes>,
+    record: String,
+}
+
+/// Raw RLP items of a list payload
+fn rlp_items(mut buf: &[u8]) -> Result<Vec<&[u8]>, NodeRecordError> {
+    let mut items = Vec::new();
+    while !buf.is_empty() {
+        let start = buf;
+        let header = Header::decode(&mut buf)?;
+        if buf.len() < header.payload_length {
+            return Err(fastrlp::DecodeError::InputTooShort.into());
+        }
+        buf.advance(header.payload_length);
+        items.push(&start[..start.len() - buf.len()]);
+    }
+    Ok(items)
+}
+
+impl Enr {
+    /// build signs a record of the node with `secret` as its key, listening at `ip` on the
+    /// given ports.
+    pub fn build(
+        secret: &SecretKey,
+        seq: u64,
+        ip: Option<IpAddr>,
+        tcp_port: Option<u16>,
+        udp_port: Option<u16>,
+    ) -> Self {
+        let public_key = PublicKey::from_secret_key(SECP256K1, secret);
+        let (ip_key, tcp_key, udp_key) = match ip {
+            Some(IpAddr::V6(_)) => ("ip6", "tcp6", "udp6"),
+            _ => ("ip", "tcp", "udp"),
+        };
+        let mut pairs = BTreeMap::new();
+        let mut insert = |key: &str, value: &dyn Encodable| {
+            let mut out = Vec::new();
+            value.encode(&mut out);
+            pairs.insert(key.to_string(), Bytes::from(out));
+        };
+        insert("id", &"v4".as_bytes());
+        insert("secp256k1", &public_key.serialize());
+        match ip {
+            Some(IpAddr::V4(ip)) => insert(ip_key, &ip.octets()),
+            Some(IpAddr::V6(ip)) => insert(ip_key, &ip.octets()),
+            None => {}
+        }
+        if let Some(port) = tcp_port {
+            insert(tcp_key, &port);
+        }
+        if let Some(port) = udp_port {
+            insert(udp_key, &port);
+        }
+
+        let mut content = Vec::new();
+        seq.encode(&mut content);
+        for (key, value) in &pairs {
+            key.as_bytes().encode(&mut content);
+            content.extend_from_slice(value);
+        }
+        let mut signed = Vec::new();
+        Header { list: true, payload_length: content.len() }.encode(&mut signed);
+        signed.extend_from_slice(&content);
+        let message = Message::from_slice(keccak256(&signed).as_bytes()).expect("hash is 32 bytes");
+        let signature = SECP256K1.sign_ecdsa(&message, secret).serialize_compact();
+
+        let mut payload = Vec::new();
+        (&signature[..]).encode(&mut payload);
+        payload.extend_from_slice(&content);
+        let mut data = Vec::new();
+        Header { list: true, payload_length: payload.len() }.encode(&mut data);
+        data.extend_from_slice(&payload);
+        let record = format!("enr:{}", base64::encode_config(&data, base64::URL_SAFE_NO_PAD));
+        Self { seq, public_key, pairs, record }
+    }
+
+    fn value<T: Decodable>(&self, key: &str) -> Result<Option<T>, NodeRecordError> {
+        match self.pairs.get(key) {
+            Some(value) => Ok(Some(T::decode(&mut &value[..])?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Node ID, the keccak256 hash of the uncompressed public key
+    pub fn node_id(&self) -> H256 {
+        keccak256(&self.public_key.serialize_uncompressed()[1..])
+    }
+
+    pub fn ip(&self) -> Option<Ipv4Addr> {
+        let ip: Bytes = self.value("ip").ok()??;
+        <[u8; 4]>::try_from(&ip[..]).ok().map(Ipv4Addr::from)
+    }
+
+    pub fn ip6(&self) -> Option<Ipv6Addr> {
+        let ip: Bytes = self.value("ip6").ok()??;
+        <[u8; 16]>::try_from(&ip[..]).ok().map(Ipv6Addr::from)
+    }
+
+    pub fn tcp_port(&self) -> Option<u16> {
+        self.value("tcp").ok()?
+    }
+
+    pub fn udp_port(&self) -> Option<u16> {
+        self.value("udp").ok()?
+    }
+
+    /// to_enode gives the enode URL of the node if the record has its IPv4 address and TCP
+    /// port, the discovery port falling back to the TCP one.
+    pub fn to_enode(&self) -> Option<Enode> {
+        let tcp_port = self.tcp_port()?;
+        Some(Enode {
+            id: H512::from_slice(&self.public_key.serialize_uncompressed()[1..]),
+            ip: IpAddr::V4(self.ip()?),
+            tcp_port,
+            udp_port: self.udp_port().unwrap_or(tcp_port),
+        })
+    }
+}
+
+impl FromStr for Enr {
+    type Err = NodeRecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let data = s.strip_prefix("enr:").ok_or(NodeRecordError::UnknownScheme)?;
+        let data = base64::decode_config(data, base64::URL_SAFE_NO_PAD).map_err(NodeRecordError::InvalidBase64)?;
+        if data.len() > MAX_RECORD_SIZE {
+            return Err(NodeRecordError::RecordTooLong { len: data.len() });
+        }
+
+        let mut buf = &data[..];
+        let header = Header::decode(&mut buf)?;
+        if !header.list {
+            return Err(fastrlp::DecodeError::UnexpectedString.into());
+        }
+        if header.payload_length != buf.len() {
+            return Err(fastrlp::DecodeError::UnexpectedLength.into());
+        }
+        let signature = Bytes::decode(&mut buf)?;
+        // the signature covers the RLP list of the rest of the record
+        let content = buf;
+        let seq = u64::decode(&mut buf)?;
+        let items = rlp_items(buf)?;
+        if items.len() % 2 != 0 {
+            return Err(fastrlp::DecodeError::UnexpectedLength.into());
+        }
+
+        let mut pairs = BTreeMap::new();
+        let mut last_key: Option<String> = None;
+        for pair in items.chunks(2) {
+            let key = String::decode(&mut &pair[0][..])?;
+            if last_key.as_ref().map_or(false, |last| *last >= key) {
+                return Err(NodeRecordError::UnsortedKeys);
+            }
+            last_key = Some(key.clone());
+            pairs.insert(key, Bytes::copy_from_slice(pair[1]));
+        }
+
+        let id = match pairs.get("id") {
+            Some(id) => String::decode(&mut &id[..])?,
+            None => return Err(NodeRecordError::MissingKey("id")),
+        };
+        if id != "v4" {
+            return Err(NodeRecordError::UnsupportedIdentity(id));
+        }
+        let public_key = match pairs.get("secp256k1") {
+            Some(key) => Bytes::decode(&mut &key[..])?,
+            None => return Err(NodeRecordError::MissingKey("secp256k1")),
+        };
+        let public_key = PublicKey::from_slice(&public_key).map_err(|_| NodeRecordError::InvalidPublicKey)?;
+
+        let mut signed = Vec::new();
+        Header { list: true, payload_length: content.len() }.encode(&mut signed);
+        signed.extend_from_slice(content);
+        let signature = Signature::from_compact(&signature).map_err(|_| NodeRecordError::WrongSignature)?;
+        let message = Message::from_slice(keccak256(&signed).as_bytes()).expect("hash is 32 bytes");
+        SECP256K1
+            .verify_ecdsa(&message, &signature, &public_key)
+            .map_err(|_| NodeRecordError::WrongSignature)?;
+
+        Ok(Self { seq, public_key, pairs, record: s.to_string() })
+    }
+}
+
+impl fmt::Display for Enr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.record)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use hex_literal::hex;
+
+    // example record of EIP-778
+    const RECORD: &str = "enr:-IS4QHCYrYZbAKWCBRlAy5zzaDZXJBGkcnh4MHcBFZntXNFrdvJjX04jRzjzCBOonrkTfj499SZuOh8R33Ls8RRcy5wBgmlkgnY0gmlwhH8AAAGJc2VjcDI1NmsxoQPKY0yuDUmstAHYpMa2_oxVtw0RW_QAdpzBQA8yWM0xOIN1ZHCCdl8";
+
+    #[test]
+    fn parse_enr() {
+        let enr: Enr = RECORD.parse().unwrap();
+        assert_eq!(1, enr.seq);
+        assert_eq!(
+            H256(hex!("a448f24c6d18e575453db13171562b71999873db5b286df957af199ec94617f7")),
+            enr.node_id()
+        );
+        assert_eq!(Some(Ipv4Addr::LOCALHOST), enr.ip());
+        assert_eq!(Some(30303), enr.udp_port());
+        assert_eq!(None, enr.tcp_port());
+        assert_eq!(None, enr.to_enode());
+        assert_eq!(RECORD, enr.to_string());
+
+        // flip a bit of the signed content
+        let mut data = base64::decode_config(&RECORD[4..], base64::URL_SAFE_NO_PAD).unwrap();
+        let last = data.len() - 1;
+        data[last] ^= 1;
+        let tampered = format!("enr:{}", base64::encode_config(&data, base64::URL_SAFE_NO_PAD));
+        assert!(matches!(tampered.parse::<Enr>(), Err(NodeRecordError::WrongSignature)));
+        assert!(matches!("enr:!!".parse::<Enr>(), Err(NodeRecordError::InvalidBase64(_))));
+    }
+
+    #[test]
+    fn build_enr() {
+        let secret =
+            SecretKey::from_slice(&hex!("b71c71a67e1177ad4e901695e1b4b9ee17ae16c6668d313eac2f96dbcda3f291")).unwrap();
+        let enr = Enr::build(&secret, 1, Some(IpAddr::V4(Ipv4Addr::LOCALHOST)), None, Some(30303));
+        assert_eq!(RECORD, enr.to_string());
+        assert_eq!(enr, RECORD.parse().unwrap());
+
+        let enr = Enr::build(&secret, 2, Some(IpAddr::V4(Ipv4Addr::LOCALHOST)), Some(30311), Some(30311));
+        let enode = enr.to_string().parse::<Enr>().unwrap().to_enode().unwrap();
+        assert_eq!((30311, 30311), (enode.tcp_port, enode.udp_port));
+        assert_eq!(enr.public_key, enode.public_key());
+    }
+}
@@ -0,0 +1,146 @@
+mod dns;
+mod enode;
+mod enr;
+mod node_key;
+mod topology;
+
+pub use dns::*;
+pub use enode::*;
+pub use enr::*;
+pub use node_key::*;
+pub use topology::*;
+
+use ethereum_types::H512;
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum NodeRecordError {
+    UnknownScheme,
+    InvalidNodeId(hex::FromHexError),
+    WrongNodeIdLen { len: usize },
+    InvalidPublicKey,
+    InvalidIp(String),
+    InvalidPort(String),
+    InvalidDiscPort(String),
+    InvalidBase64(base64::DecodeError),
+    InvalidRlp(fastrlp::DecodeError),
+    RecordTooLong { len: usize },
+    UnsortedKeys,
+    MissingKey(&'static str),
+    UnsupportedIdentity(String),
+    WrongSignature,
+}
+
+impl std::fmt::Display for NodeRecordError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::error::Error for NodeRecordError {}
+
+impl From<fastrlp::DecodeError> for NodeRecordError {
+    fn from(e: fastrlp::DecodeError) -> Self {
+        Self::InvalidRlp(e)
+    }
+}
+
+/// NodeRecord is an entry of a node list, an enode URL or an ENR.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum NodeRecord {
+    Enode(Enode),
+    Enr(Enr),
+}
+
+impl NodeRecord {
+    /// ID of the node, its uncompressed public key without the prefix byte as in enode URLs.
+    pub fn id(&self) -> H512 {
+        match self {
+            Self::Enode(enode) => enode.id,
+            Self::Enr(enr) => H512::from_slice(&enr.public_key.serialize_uncompressed()[1..]),
+        }
+    }
+}
+
+impl FromStr for NodeRecord {
+    type Err = NodeRecordError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.starts_with("enr:") {
+            Ok(Self::Enr(s.parse()?))
+        } else {
+            Ok(Self::Enode(s.parse()?))
+        }
+    }
+}
+
+/// Entry of a node list that is not a valid enode URL or ENR, with the line of the source
+/// it was read from if known.
+#[derive(Debug)]
+pub struct InvalidNode {
+    pub line: Option<usize>,
+    pub node: String,
+    pub err: NodeRecordError,
+}
+
+impl std::fmt::Display for InvalidNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "line {}: {:?}: {}", line, self.node, self.err),
+            None => write!(f, "{:?}: {}", self.node, self.err),
+        }
+    }
+}
+
+/// parse_node_list parses every entry of a node list, reporting all the invalid ones with
+/// the line of `source`, e.g. the config.toml the list was read from, they are on.
+pub fn parse_node_list(nodes: &[String], source: Option<&str>) -> Result<Vec<NodeRecord>, Vec<InvalidNode>> {
+    let mut records = Vec::new();
+    let mut invalid = Vec::new();
+    for node in nodes {
+        match node.parse() {
+            Ok(record) => records.push(record),
+            Err(err) => invalid.push(InvalidNode {
+                line: source.and_then(|source| source_line(source, node)),
+                node: node.clone(),
+                err,
+            }),
+        }
+    }
+    if invalid.is_empty() {
+        Ok(records)
+    } else {
+        Err(invalid)
+    }
+}
+
+/// First line, counted from 1, that holds the quoted string or only the string.
+fn source_line(source: &str, s: &str) -> Option<usize> {
+    let quoted = format!("\"{}\"", s);
+    source.lines().position(|line| line.contains(&quoted) || line.trim() == s).map(|i| i + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_invalid_nodes() {
+        let valid = "enode://79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8@127.0.0.1:30311";
+        let short = "enode://79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798@127.0.0.1:30312";
+        let source = format!("[Node.P2P]\nStaticNodes = [\n  \"{}\",\n  \"{}\",\n  \"enode://xyz\",\n]\n", valid, short);
+        let nodes: Vec<String> = vec![valid.into(), short.into(), "enode://xyz".into()];
+
+        let invalid = parse_node_list(&nodes, Some(&source)).unwrap_err();
+        assert_eq!(2, invalid.len());
+        assert_eq!(Some(4), invalid[0].line);
+        assert!(matches!(invalid[0].err, NodeRecordError::WrongNodeIdLen { len: 32 }));
+        assert_eq!(Some(5), invalid[1].line);
+        assert!(matches!(invalid[1].err, NodeRecordError::InvalidNodeId(_)));
+
+        assert_eq!(1, parse_node_list(&nodes[..1], None).unwrap().len());
+
+        let source = format!("{}\nenode://xyz\n", valid);
+        assert_eq!(Some(2), parse_node_list(&nodes, Some(&source)).unwrap_err()[1].line);
+    }
+}
@@ -0,0 +1,140 @@
+use super::Enr;
+use crate::models::bsc::TomlConfig;
+use anyhow::{bail, Context};
+use secp256k1::{PublicKey, SecretKey, SECP256K1};
+use std::{
+    fs,
+    net::IpAddr,
+    path::{Path, PathBuf},
+};
+
+/// Parses a geth nodekey, the hex secp256k1 secret key of a node.
+pub fn parse_node_key(s: &str) -> anyhow::Result<SecretKey> {
+    let key = hex::decode(s.trim().trim_start_matches("0x"))?;
+    if key.len() != 32 {
+        bail!("node key must be 32 bytes long, got {}", key.len());
+    }
+    Ok(SecretKey::from_slice(&key)?)
+}
+
+/// Reads a geth nodekey file, as found at `<datadir>/geth/nodekey`.
+pub fn read_node_key(path: impl AsRef<Path>) -> anyhow::Result<SecretKey> {
+    let path = path.as_ref();
+    parse_node_key(&fs::read_to_string(path)?).with_context(|| format!("invalid node key in {:?}", path))
+}
+
+/// IP and port of a geth `ListenAddr`. The IP is None if left out, as in `:30303`, or
+/// unspecified, as in `0.0.0.0:30303`, where the node listens on every interface.
+pub fn parse_listen_addr(addr: &str) -> anyhow::Result<(Option<IpAddr>, u16)> {
+    let (host, port) = match addr.rsplit_once(':') {
+        Some(parts) => parts,
+        None => bail!("no port in listen address {:?}", addr),
+    };
+    let port = port.parse().with_context(|| format!("invalid port in listen address {:?}", addr))?;
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    if host.is_empty() {
+        return Ok((None, port));
+    }
+    let ip: IpAddr = host.parse().with_context(|| format!("invalid IP in listen address {:?}", addr))?;
+    Ok((if ip.is_unspecified() { None } else { Some(ip) }, port))
+}
+
+/// `enode://<id>@<ip>:<port>` URL of a node, its ID being the uncompressed public key of
+/// its node key without the prefix byte.
+pub fn enode_url(node_key: &SecretKey, ip: IpAddr, port: u16) -> String {
+    let id = PublicKey::from_secret_key(SECP256K1, node_key).serialize_uncompressed();
+    let host = match ip {
+        IpAddr::V4(ip) => ip.to_string(),
+        IpAddr::V6(ip) => format!("[{}]", ip),
+    };
+    format!("enode://{}@{}:{}", hex::encode(&id[1..]), host, port)
+}
+
+/// node_enode derives the enode URL of a node from its nodekey file and the `ListenAddr` in
+/// its config, `ip` standing in for a listen address without one.
+pub fn node_enode(node_key: impl AsRef<Path>, config: &TomlConfig, ip: IpAddr) -> anyhow::Result<String> {
+    let (listen_ip, port) = parse_listen_addr(&config.node.p2p.listen_addr)?;
+    Ok(enode_url(&read_node_key(node_key)?, listen_ip.unwrap_or(ip), port))
+}
+
+/// Location of the nodekey in a node dir, a geth datadir or a dir holding the nodekey itself.
+pub fn node_key_path(dir: &Path) -> Option<PathBuf> {
+    [dir.join("geth").join("nodekey"), dir.join("nodekey")].into_iter().find(|path| path.is_file())
+}
+
+/// Node read from its dir: its node key, its config and the address in its `ListenAddr`.
+pub struct NodeDir {
+    pub path: PathBuf,
+    pub config: TomlConfig,
+    pub node_key: SecretKey,
+    pub listen_ip: Option<IpAddr>,
+    pub port: u16,
+}
+
+impl NodeDir {
+    pub fn enode(&self, ip: IpAddr) -> String {
+        enode_url(&self.node_key, self.listen_ip.unwrap_or(ip), self.port)
+    }
+
+    /// Signed node record of the node, listening on the same port for TCP and discovery.
+    pub fn enr(&self, ip: IpAddr, seq: u64) -> Enr {
+        Enr::build(&self.node_key, seq, Some(self.listen_ip.unwrap_or(ip)), Some(self.port), Some(self.port))
+    }
+}
+
+/// read_node_dirs scans the node dirs of a cluster, in name order, for the ones with a
+/// nodekey and a `config.toml`.
+pub fn read_node_dirs(dir: impl AsRef<Path>) -> anyhow::Result<Vec<NodeDir>> {
+    let mut dirs: Vec<_> = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<Result<_, _>>()?;
+    dirs.sort();
+
+    let mut nodes = Vec::new();
+    for dir in dirs.iter().filter(|path| path.is_dir()) {
+        let node_key = match node_key_path(dir) {
+            Some(path) => path,
+            None => continue,
+        };
+        let path = dir.join("config.toml");
+        if !path.is_file() {
+            bail!("no config.toml next to {:?}", node_key);
+        }
+        let config: TomlConfig = toml::from_str(&fs::read_to_string(&path)?)?;
+        let (listen_ip, port) = parse_listen_addr(&config.node.p2p.listen_addr)?;
+        nodes.push(NodeDir { path: dir.clone(), config, node_key: read_node_key(node_key)?, listen_ip, port });
+    }
+    Ok(nodes)
+}
+
+/// cluster_enodes returns the enode URLs of the node dirs of a cluster, `ip` standing in for
+/// listen addresses without one.
+pub fn cluster_enodes(dir: impl AsRef<Path>, ip: IpAddr) -> anyhow::Result<Vec<String>> {
+    Ok(read_node_dirs(dir)?.iter().map(|node| node.enode(ip)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::Ipv4Addr;
+
+    #[test]
+    fn derive_enode_url() {
+        let node_key =
+            parse_node_key("0000000000000000000000000000000000000000000000000000000000000001\n").unwrap();
+        assert_eq!(
+            "enode://79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798483ada7726a3c4655da4fbfc0e1108a8fd17b448a68554199c47d08ffb10d4b8@127.0.0.1:30311",
+            enode_url(&node_key, IpAddr::V4(Ipv4Addr::LOCALHOST), 30311)
+        );
+        assert!(parse_node_key("0001").is_err());
+
+        assert_eq!((None, 30311), parse_listen_addr(":30311").unwrap());
+        assert_eq!((None, 30311), parse_listen_addr("0.0.0.0:30311").unwrap());
+        assert_eq!(
+            (Some(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1))), 30311),
+            parse_listen_addr("10.0.0.1:30311").unwrap()
+        );
+        assert_eq!(Some("::1".parse().unwrap()), parse_listen_addr("[::1]:30311").unwrap().0);
+        assert!(parse_listen_addr("30311").is_err());
+    }
+}
@@ -0,0 +1,389 @@
+use super::{Enode, NodeDir};
+use ethereum_types::{Address, H512};
+use secp256k1::{PublicKey, SECP256K1};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt::Write,
+    net::IpAddr,
+};
+
+/// Node of a cluster topology
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TopologyNode {
+    pub name: String,
+    pub id: H512,
+    pub listen_ip: Option<IpAddr>,
+    pub port: u16,
+    /// accounts of the node's keystore, among which the validator it seals as
+    pub accounts: Vec<Address>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PeerKind {
+    Static,
+    Trusted,
+}
+
+/// Node a peering entry points at
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeerTarget {
+    /// a node of the cluster, and whether the entry has the IP and port it listens on
+    Node { index: usize, reachable: bool },
+    /// a node ID of no node of the cluster
+    Unknown(Enode),
+    /// an entry that is not an enode URL
+    Invalid(String),
+}
+
+/// Entry of a node's `StaticNodes` or `TrustedNodes`
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Peering {
+    pub from: usize,
+    pub kind: PeerKind,
+    pub entry: String,
+    pub target: PeerTarget,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TopologyIssue {
+    InvalidPeer { node: String, entry: String, err: String },
+    UnknownPeer { node: String, peer: Enode },
+    WrongPeerAddress { node: String, peer: String, entry: String },
+    DuplicateNodeId { id: H512, nodes: Vec<String> },
+    PortConflict { port: u16, nodes: Vec<String> },
+    Partition { components: Vec<Vec<String>> },
+    MissingValidator { validator: Address },
+    UnreachableValidator { validator: Address, node: String },
+}
+
+/// Node ID shortened to its first and last bytes, as in the node logs of geth.
+fn short_id(id: &H512) -> String {
+    let id = hex::encode(id);
+    format!("{}...{}", &id[..4], &id[id.len() - 4..])
+}
+
+impl std::fmt::Display for TopologyIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TopologyIssue::InvalidPeer { node, entry, err } => {
+                write!(f, "{} has a peer entry that is no enode URL: {}: {}", node, entry, err)
+            }
+            TopologyIssue::UnknownPeer { node, peer } => write!(
+                f,
+                "{} dials {}@{}:{}, which is no node of the cluster",
+                node,
+                short_id(&peer.id),
+                peer.ip,
+                peer.tcp_port
+            ),
+            TopologyIssue::WrongPeerAddress { node, peer, entry } => {
+                write!(f, "{} dials {} at a wrong address: {}", node, peer, entry)
+            }
+            TopologyIssue::DuplicateNodeId { id, nodes } => {
+                write!(f, "{} share the node ID {}", nodes.join(", "), short_id(id))
+            }
+            TopologyIssue::PortConflict { port, nodes } => {
+                write!(f, "{} all listen on port {}", nodes.join(", "), port)
+            }
+            TopologyIssue::Partition { components } => {
+                let components: Vec<String> = components.iter().map(|c| format!("[{}]", c.join(", "))).collect();
+                write!(f, "the cluster is split into {} parts: {}", components.len(), components.join(" "))
+            }
+            TopologyIssue::MissingValidator { validator } => {
+                write!(f, "validator {:?} is the account of no node", validator)
+            }
+            TopologyIssue::UnreachableValidator { validator, node } => {
+                write!(f, "validator {:?} on {} is cut off from the other validators", validator, node)
+            }
+        }
+    }
+}
+
+/// Topology is the graph of the static and trusted peering of the nodes of a cluster, as
+/// set in their `config.toml`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Topology {
+    pub nodes: Vec<TopologyNode>,
+    pub peerings: Vec<Peering>,
+}
+
+impl Topology {
+    /// new builds the topology of the node dirs, `accounts` holding the accounts of each of
+    /// them and `ip` standing in for listen addresses without one.
+    pub fn new(dirs: &[NodeDir], accounts: Vec<Vec<Address>>, ip: IpAddr) -> Self {
+        let nodes: Vec<TopologyNode> = dirs
+            .iter()
+            .zip(accounts.into_iter().chain(std::iter::repeat(vec![])))
+            .map(|(dir, accounts)| TopologyNode {
+                name: dir.path.file_name().unwrap_or(dir.path.as_os_str()).to_string_lossy().into_owned(),
+                id: H512::from_slice(&PublicKey::from_secret_key(SECP256K1, &dir.node_key).serialize_uncompressed()[1..]),
+                listen_ip: dir.listen_ip,
+                port: dir.port,
+                accounts,
+            })
+            .collect();
+
+        let mut peerings = Vec::new();
+        for (from, dir) in dirs.iter().enumerate() {
+            let p2p = &dir.config.node.p2p;
+            let entries = p2p
+                .static_nodes
+                .iter()
+                .map(|entry| (PeerKind::Static, entry))
+                .chain(p2p.trusted_nodes.iter().map(|entry| (PeerKind::Trusted, entry)));
+            for (kind, entry) in entries {
+                let target = match entry.parse::<Enode>() {
+                    Err(err) => PeerTarget::Invalid(err.to_string()),
+                    Ok(enode) => {
+                        let reachable = |node: &TopologyNode| {
+                            node.port == enode.tcp_port && node.listen_ip.unwrap_or(ip) == enode.ip
+                        };
+                        // of nodes sharing the ID, prefer the one at the entry's address
+                        let same_id = || nodes.iter().enumerate().filter(|(_, node)| node.id == enode.id);
+                        match same_id().find(|(_, node)| reachable(node)).or_else(|| same_id().next()) {
+                            Some((index, node)) => PeerTarget::Node { index, reachable: reachable(node) },
+                            None => PeerTarget::Unknown(enode),
+                        }
+                    }
+                };
+                peerings.push(Peering { from, kind, entry: entry.clone(), target });
+            }
+        }
+        Self { nodes, peerings }
+    }
+
+    /// components groups the nodes that can reach each other through static peering, a
+    /// connection dialed by either end being used both ways.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut parent: Vec<usize> = (0..self.nodes.len()).collect();
+        fn root(parent: &mut Vec<usize>, mut i: usize) -> usize {
+            while parent[i] != i {
+                parent[i] = parent[parent[i]];
+                i = parent[i];
+            }
+            i
+        }
+        for peering in &self.peerings {
+            if let (PeerKind::Static, PeerTarget::Node { index, reachable: true }) = (peering.kind, &peering.target) {
+                let (a, b) = (root(&mut parent, peering.from), root(&mut parent, *index));
+                parent[a] = b;
+            }
+        }
+        let mut components: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+        for i in 0..self.nodes.len() {
+            let r = root(&mut parent, i);
+            components.entry(r).or_default().push(i);
+        }
+        let mut components: Vec<Vec<usize>> = components.into_values().collect();
+        components.sort();
+        components
+    }
+
+    fn names(&self, indexes: &[usize]) -> Vec<String> {
+        indexes.iter().map(|i| self.nodes[*i].name.clone()).collect()
+    }
+
+    /// issues flags what keeps the nodes, and the given validators, from reaching each other.
+    pub fn issues(&self, validators: &[Address]) -> Vec<TopologyIssue> {
+        let mut issues = Vec::new();
+        for peering in &self.peerings {
+            let node = self.nodes[peering.from].name.clone();
+            match &peering.target {
+                PeerTarget::Invalid(err) => {
+                    issues.push(TopologyIssue::InvalidPeer { node, entry: peering.entry.clone(), err: err.clone() })
+                }
+                PeerTarget::Unknown(peer) => issues.push(TopologyIssue::UnknownPeer { node, peer: *peer }),
+                PeerTarget::Node { index, reachable: false } => issues.push(TopologyIssue::WrongPeerAddress {
+                    node,
+                    peer: self.nodes[*index].name.clone(),
+                    entry: peering.entry.clone(),
+                }),
+                PeerTarget::Node { .. } => {}
+            }
+        }
+
+        let mut ids: BTreeMap<H512, Vec<usize>> = BTreeMap::new();
+        let mut ports: BTreeMap<u16, Vec<usize>> = BTreeMap::new();
+        for (i, node) in self.nodes.iter().enumerate() {
+            ids.entry(node.id).or_default().push(i);
+            ports.entry(node.port).or_default().push(i);
+        }
+        for (id, nodes) in ids.into_iter().filter(|(_, nodes)| nodes.len() > 1) {
+            issues.push(TopologyIssue::DuplicateNodeId { id, nodes: self.names(&nodes) });
+        }
+        for (port, nodes) in ports {
+            // nodes listening on every interface clash with any other on the port
+            let conflicting: Vec<usize> = nodes
+                .iter()
+                .copied()
+                .filter(|i| {
+                    nodes.iter().any(|j| {
+                        let (a, b) = (self.nodes[*i].listen_ip, self.nodes[*j].listen_ip);
+                        i != j && (a.is_none() || b.is_none() || a == b)
+                    })
+                })
+                .collect();
+            if !conflicting.is_empty() {
+                issues.push(TopologyIssue::PortConflict { port, nodes: self.names(&conflicting) });
+            }
+        }
+
+        let components = self.components();
+        if components.len() > 1 {
+            issues.push(TopologyIssue::Partition {
+                components: components.iter().map(|c| self.names(c)).collect(),
+            });
+        }
+
+        // validators are expected in the component holding most of them
+        let validator_nodes = |component: &Vec<usize>| {
+            component.iter().filter(|i| self.nodes[**i].accounts.iter().any(|a| validators.contains(a))).count()
+        };
+        let main = components.iter().max_by_key(|c| (validator_nodes(c), std::cmp::Reverse(c[0])));
+        for validator in validators.iter().collect::<BTreeSet<_>>() {
+            let nodes: Vec<usize> =
+                (0..self.nodes.len()).filter(|i| self.nodes[*i].accounts.contains(validator)).collect();
+            if nodes.is_empty() {
+                issues.push(TopologyIssue::MissingValidator { validator: *validator });
+            }
+            for i in nodes.into_iter().filter(|i| !main.map_or(false, |c| c.contains(i))) {
+                issues.push(TopologyIssue::UnreachableValidator {
+                    validator: *validator,
+                    node: self.nodes[i].name.clone(),
+                });
+            }
+        }
+        issues
+    }
+
+    /// to_dot writes the graph in the DOT language: validators are boxes, trusted peering is
+    /// dashed, and entries that can't be dialed are red.
+    pub fn to_dot(&self, validators: &[Address]) -> String {
+        let mut dot = String::from("digraph cluster {\n");
+        for node in &self.nodes {
+            let validator = node.accounts.iter().find(|a| validators.contains(a));
+            let mut label = format!("{}\\n:{}", node.name, node.port);
+            if let Some(validator) = validator {
+                write!(label, "\\n{:?}", validator).unwrap();
+            }
+            let shape = if validator.is_some() { "box" } else { "ellipse" };
+            writeln!(dot, "    \"{}\" [label=\"{}\", shape={}];", node.name, label, shape).unwrap();
+        }
+        for peering in &self.peerings {
+            let from = &self.nodes[peering.from].name;
+            let mut attrs = Vec::new();
+            if peering.kind == PeerKind::Trusted {
+                attrs.push(String::from("style=dashed"));
+            }
+            let to = match &peering.target {
+                PeerTarget::Node { index, reachable } => {
+                    if !reachable {
+                        attrs.push(String::from("color=red, label=\"wrong address\""));
+                    }
+                    self.nodes[*index].name.clone()
+                }
+                PeerTarget::Unknown(enode) => {
+                    let name = format!("unknown {}", &hex::encode(enode.id)[..8]);
+                    writeln!(dot, "    \"{}\" [color=red];", name).unwrap();
+                    attrs.push(String::from("color=red"));
+                    name
+                }
+                PeerTarget::Invalid(_) => continue,
+            };
+            let attrs = if attrs.is_empty() { String::new() } else { format!(" [{}]", attrs.join(", ")) };
+            writeln!(dot, "    \"{}\" -> \"{}\"{};", from, to, attrs).unwrap();
+        }
+        dot.push_str("}\n");
+        dot
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::bsc::*;
+    use crate::p2p::enode_url;
+    use secp256k1::SecretKey;
+    use std::net::Ipv4Addr;
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::LOCALHOST);
+
+    fn node_dir(name: &str, key: u8, port: u16, static_nodes: Vec<String>) -> NodeDir {
+        NodeDir {
+            path: name.into(),
+            config: TomlConfig {
+                node: TomlNode {
+                    p2p: TomlP2P {
+                        no_discovery: true,
+                        static_nodes,
+                        listen_addr: format!(":{}", port),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            node_key: SecretKey::from_slice(&[key; 32]).unwrap(),
+            listen_ip: None,
+            port,
+        }
+    }
+
+    fn enode(key: u8, port: u16) -> String {
+        enode_url(&SecretKey::from_slice(&[key; 32]).unwrap(), IP, port)
+    }
+
+    #[test]
+    fn flag_topology_issues() {
+        let validators = [Address::repeat_byte(1), Address::repeat_byte(2), Address::repeat_byte(3)];
+        let accounts = vec![vec![validators[0]], vec![validators[1]], vec![], vec![]];
+        let dirs = vec![
+            node_dir("node0", 1, 30311, vec![enode(2, 30312)]),
+            // dials node0 on a wrong port, and a node out of the cluster
+            node_dir("node1", 2, 30312, vec![enode(1, 30399), enode(9, 30319)]),
+            node_dir("node2", 3, 30313, vec![]),
+            // same key as node2, and the port of node0
+            node_dir("node3", 3, 30311, vec![enode(3, 30313)]),
+        ];
+        let topology = Topology::new(&dirs, accounts, IP);
+        assert_eq!(vec![vec![0, 1], vec![2, 3]], topology.components());
+
+        let issues = topology.issues(&validators);
+        assert!(issues.contains(&TopologyIssue::WrongPeerAddress {
+            node: String::from("node1"),
+            peer: String::from("node0"),
+            entry: enode(1, 30399),
+        }));
+        assert!(issues.iter().any(|issue| matches!(issue, TopologyIssue::UnknownPeer { node, .. } if node == "node1")));
+        assert!(issues.iter().any(|issue| matches!(issue, TopologyIssue::DuplicateNodeId { nodes, .. } if *nodes == ["node2", "node3"])));
+        assert!(issues.contains(&TopologyIssue::PortConflict {
+            port: 30311,
+            nodes: vec![String::from("node0"), String::from("node3")],
+        }));
+        assert!(issues.iter().any(|issue| matches!(issue, TopologyIssue::Partition { components } if components.len() == 2)));
+        assert!(issues.contains(&TopologyIssue::MissingValidator { validator: validators[2] }));
+        assert!(!issues.iter().any(|issue| matches!(issue, TopologyIssue::UnreachableValidator { .. })));
+
+        let messages: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+        assert!(messages.contains(&format!("node1 dials node0 at a wrong address: {}", enode(1, 30399))));
+        assert!(messages.contains(&String::from("node0, node3 all listen on port 30311")));
+        assert!(messages.contains(&String::from("the cluster is split into 2 parts: [node0, node1] [node2, node3]")));
+        let unknown = messages.iter().find(|m| m.starts_with("node1 dials ") && m.contains("@127.0.0.1:30319")).unwrap();
+        assert!(unknown.ends_with(", which is no node of the cluster") && unknown.contains("..."));
+
+        let issues = Topology::new(&dirs, vec![vec![], vec![], vec![validators[0]], vec![validators[1]]], IP)
+            .issues(&validators[..2]);
+        assert!(!issues.iter().any(|issue| matches!(issue, TopologyIssue::UnreachableValidator { .. })));
+        let issues = Topology::new(&dirs, vec![vec![validators[0]], vec![], vec![validators[1]], vec![]], IP)
+            .issues(&validators[..2]);
+        assert!(issues.contains(&TopologyIssue::UnreachableValidator {
+            validator: validators[1],
+            node: String::from("node2"),
+        }));
+
+        let dot = topology.to_dot(&validators);
+        assert!(dot.contains("\"node0\" -> \"node1\";"));
+        assert!(dot.contains("\"node1\" -> \"node0\" [color=red, label=\"wrong address\"];"));
+        assert!(dot.contains("shape=box"));
+    }
+}
@@ -0,0 +1,306 @@
+//! The system contract upgrades BSC geth applies at its hard forks, embedded by network or
+//! read from its `core/systemcontracts` tables: `<fork>/<network>/<contract>` files of hex
+//! bytecode.
+
+use crate::models::chainspec::{BlockNumber, ChainSpec, Contract, Upgrades};
+use anyhow::{bail, Context};
+use bytes::Bytes;
+use ethereum_types::Address;
+use std::{collections::BTreeMap, fs, path::Path};
+
+/// System contracts by the name of their bytecode file.
+pub const SYSTEM_CONTRACTS: [(&str, u64); 17] = [
+    ("ValidatorContract", 0x1000),
+    ("SlashContract", 0x1001),
+    ("SystemRewardContract", 0x1002),
+    ("LightClientContract", 0x1003),
+    ("TokenHubContract", 0x1004),
+    ("RelayerIncentivizeContract", 0x1005),
+    ("RelayerHubContract", 0x1006),
+    ("GovHubContract", 0x1007),
+    ("TokenManagerContract", 0x1008),
+    ("CrossChainContract", 0x2000),
+    ("StakingContract", 0x2001),
+    ("StakeHubContract", 0x2002),
+    ("StakeCreditContract", 0x2003),
+    ("GovernorContract", 0x2004),
+    ("GovTokenContract", 0x2005),
+    ("TimelockContract", 0x2006),
+    ("TokenRecoverPortalContract", 0x3000),
+];
+
+/// Block forks of the chain spec upgrading system contracts, with their directory in geth's
+/// tables. Boneh and Lynn are the fast finality forks geth calls Luban and Plato.
+pub const UPGRADE_FORKS: [(&str, &str); 11] = [
+    ("ramanujan", "ramanujan"),
+    ("niels", "niels"),
+    ("mirrorsync", "mirror"),
+    ("bruno", "bruno"),
+    ("euler", "euler"),
+    ("gibbs", "gibbs"),
+    ("moran", "moran"),
+    ("planck", "planck"),
+    ("boneh", "luban"),
+    ("lynn", "plato"),
+    ("hertz", "hertz"),
+];
+
+/// Upgrade tables built into the tools by network, one `<fork> <contract> <hex bytecode>` line
+/// per upgraded contract.
+const EMBEDDED_TABLES: [(&str, &str); 4] = [
+    ("mainnet", include_str!("systemcontracts/mainnet.txt")),
+    ("chapel", include_str!("systemcontracts/chapel.txt")),
+    ("rialto", include_str!("systemcontracts/rialto.txt")),
+    ("default", include_str!("systemcontracts/default.txt")),
+];
+
+/// network is the directory of geth's tables for the chain, by chain id.
+pub fn network(chain_id: u64) -> &'static str {
+    match chain_id {
+        56 => "mainnet",
+        97 => "chapel",
+        1417 => "rialto",
+        _ => "default",
+    }
+}
+
+pub fn contract_address(name: &str) -> Option<Address> {
+    SYSTEM_CONTRACTS
+        .iter()
+        .find(|(contract, _)| *contract == name)
+        .map(|(_, address)| Address::from_low_u64_be(*address))
+}
+
+fn fork_block(upgrades: &Upgrades, fork: &str) -> Option<BlockNumber> {
+    match fork {
+        "ramanujan" => upgrades.ramanujan,
+        "niels" => upgrades.niels,
+        "mirrorsync" => upgrades.mirrorsync,
+        "bruno" => upgrades.bruno,
+        "euler" => upgrades.euler,
+        "gibbs" => upgrades.gibbs,
+        "moran" => upgrades.moran,
+        "planck" => upgrades.planck,
+        "boneh" => upgrades.boneh,
+        "lynn" => upgrades.lynn,
+        "hertz" => upgrades.hertz,
+        _ => None,
+    }
+}
+
+/// SystemContractRegistry holds the upgraded bytecode of one network, by fork and contract.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SystemContractRegistry {
+    pub network: String,
+    pub upgrades: BTreeMap<&'static str, BTreeMap<String, Bytes>>,
+}
+
+impl SystemContractRegistry {
+    /// load reads the tables of `network` from a `core/systemcontracts` directory of BSC geth,
+    /// forks without a directory for the network upgrading nothing.
+    pub fn load(dir: &Path, network: &str) -> anyhow::Result<Self> {
+        let mut registry = Self { network: network.into(), upgrades: BTreeMap::new() };
+        for (fork, fork_dir) in UPGRADE_FORKS {
+            let path = dir.join(fork_dir).join(network);
+            if !path.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&path)? {
+                let path = entry?.path();
+                let name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string();
+                let code = fs::read_to_string(&path).with_context(|| format!("reading {}", path.display()))?;
+                registry.insert(fork, &name, &code).with_context(|| format!("in {}", path.display()))?;
+            }
+        }
+        Ok(registry)
+    }
+
+    /// embedded is the registry of `network` built into the tools.
+    pub fn embedded(network: &str) -> anyhow::Result<Self> {
+        match EMBEDDED_TABLES.iter().find(|(name, _)| *name == network) {
+            Some((_, table)) => Self::parse(network, table).with_context(|| format!("embedded {} table", network)),
+            None => bail!("no system contract table for network {}", network),
+        }
+    }
+
+    /// parse reads an upgrade table of `network`, skipping blank lines and `#` comments.
+    pub fn parse(network: &str, table: &str) -> anyhow::Result<Self> {
+        let mut registry = Self { network: network.into(), upgrades: BTreeMap::new() };
+        for (i, line) in table.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (fork, name, code) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+                [fork, name, code] => (fork, name, code),
+                _ => bail!("line {}: expected <fork> <contract> <hex bytecode>", i + 1),
+            };
+            let fork = match UPGRADE_FORKS.iter().find(|(f, _)| *f == fork) {
+                Some((fork, _)) => *fork,
+                None => bail!("line {}: {} does not upgrade system contracts", i + 1, fork),
+            };
+            registry.insert(fork, name, code).with_context(|| format!("line {}", i + 1))?;
+        }
+        Ok(registry)
+    }
+
+    /// insert adds the hex bytecode `code` of the contract `name` upgraded at `fork`.
+    pub fn insert(&mut self, fork: &'static str, name: &str, code: &str) -> anyhow::Result<()> {
+        if !UPGRADE_FORKS.iter().any(|(f, _)| *f == fork) {
+            bail!("{} does not upgrade system contracts", fork);
+        }
+        if contract_address(name).is_none() {
+            bail!("unknown system contract {}", name);
+        }
+        let code = hex::decode(code.trim().trim_start_matches("0x"))?;
+        self.upgrades.entry(fork).or_default().insert(name.into(), code.into());
+        Ok(())
+    }
+}
+
+/// What became of the upgrades of a fork.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum UpgradeEffect {
+    /// the bytecode is set at the fork block
+    Scheduled(BlockNumber),
+    /// the fork is on at genesis, whose alloc holds the code, as geth never upgrades the genesis
+    AtGenesis(BlockNumber),
+    /// the fork is off
+    Off,
+}
+
+/// SystemContractUpgrade is the upgrade of system contracts at a fork.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SystemContractUpgrade {
+    pub fork: &'static str,
+    pub contracts: Vec<String>,
+    pub effect: UpgradeEffect,
+}
+
+/// apply_system_contract_upgrades sets the upgraded bytecode of the registry in the chain
+/// spec contracts at each fork block after genesis, and returns the upgrade of every fork.
+pub fn apply_system_contract_upgrades(
+    chain_spec: &mut ChainSpec,
+    registry: &SystemContractRegistry,
+) -> Vec<SystemContractUpgrade> {
+    let genesis = chain_spec.genesis.number;
+    let mut upgrades = Vec::new();
+    for (fork, contracts) in &registry.upgrades {
+        let effect = match fork_block(&chain_spec.upgrades, fork) {
+            Some(block) if block > genesis => {
+                let entry = chain_spec.contracts.entry(block).or_default();
+                for (name, code) in contracts {
+                    if let Some(address) = contract_address(name) {
+                        entry.insert(address, Contract::Contract { code: code.clone() });
+                    }
+                }
+                UpgradeEffect::Scheduled(block)
+            }
+            Some(block) => UpgradeEffect::AtGenesis(block),
+            None => UpgradeEffect::Off,
+        };
+        upgrades.push(SystemContractUpgrade { fork, contracts: contracts.keys().cloned().collect(), effect });
+    }
+    upgrades.sort_by_key(|upgrade| UPGRADE_FORKS.iter().position(|(fork, _)| *fork == upgrade.fork));
+    upgrades
+}
+
+/// unfilled_upgrades lists the forks upgrading system contracts after genesis in the chain spec
+/// for which the registry holds no bytecode.
+pub fn unfilled_upgrades(chain_spec: &ChainSpec, registry: &SystemContractRegistry) -> Vec<&'static str> {
+    UPGRADE_FORKS
+        .iter()
+        .map(|(fork, _)| *fork)
+        .filter(|fork| matches!(fork_block(&chain_spec.upgrades, fork), Some(block) if block > chain_spec.genesis.number))
+        .filter(|fork| !registry.upgrades.contains_key(fork))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::parlia::tests::chain_spec;
+
+    #[test]
+    fn schedule_upgrades() {
+        let mut registry = SystemContractRegistry { network: network(97).into(), ..Default::default() };
+        registry.insert("ramanujan", "ValidatorContract", "0x6001").unwrap();
+        registry.insert("niels", "ValidatorContract", "6002").unwrap();
+        registry.insert("niels", "CrossChainContract", "6003\n").unwrap();
+        registry.insert("mirrorsync", "TokenHubContract", "6004").unwrap();
+        registry.insert("bruno", "RelayerIncentivizeContract", "6005").unwrap();
+        assert!(registry.insert("niels", "UnknownContract", "60").is_err());
+        assert!(registry.insert("london", "ValidatorContract", "60").is_err());
+
+        let mut chain_spec = chain_spec(3, 200);
+        chain_spec.upgrades.niels = Some(BlockNumber(100));
+        chain_spec.upgrades.mirrorsync = Some(BlockNumber(200));
+        let upgrades = apply_system_contract_upgrades(&mut chain_spec, &registry);
+        let effects = upgrades.iter().map(|upgrade| (upgrade.fork, upgrade.effect)).collect::<Vec<_>>();
+        assert_eq!(
+            vec![
+                ("ramanujan", UpgradeEffect::AtGenesis(BlockNumber(0))),
+                ("niels", UpgradeEffect::Scheduled(BlockNumber(100))),
+                ("mirrorsync", UpgradeEffect::Scheduled(BlockNumber(200))),
+                ("bruno", UpgradeEffect::Off),
+            ],
+            effects
+        );
+        assert_eq!(vec![String::from("CrossChainContract"), String::from("ValidatorContract")], upgrades[1].contracts);
+
+        assert!(!chain_spec.contracts.contains_key(&BlockNumber(0)));
+        let niels = &chain_spec.contracts[&BlockNumber(100)];
+        assert_eq!(Some(&Contract::Contract { code: Bytes::from_static(&[0x60, 0x02]) }), niels.get(&Address::from_low_u64_be(0x1000)));
+        assert_eq!(Some(&Contract::Contract { code: Bytes::from_static(&[0x60, 0x03]) }), niels.get(&Address::from_low_u64_be(0x2000)));
+        assert_eq!(1, chain_spec.contracts[&BlockNumber(200)].len());
+
+        assert!(unfilled_upgrades(&chain_spec, &registry).is_empty());
+        chain_spec.upgrades.planck = Some(BlockNumber(300));
+        chain_spec.upgrades.hertz = Some(BlockNumber(400));
+        chain_spec.upgrades.gibbs = Some(BlockNumber(0));
+        assert_eq!(vec!["planck", "hertz"], unfilled_upgrades(&chain_spec, &registry));
+        registry.insert("hertz", "ValidatorContract", "6006").unwrap();
+        assert_eq!(vec!["planck"], unfilled_upgrades(&chain_spec, &registry));
+        let embedded = SystemContractRegistry::embedded("chapel").unwrap();
+        assert_eq!(vec!["niels", "mirrorsync", "planck", "hertz"], unfilled_upgrades(&chain_spec, &embedded));
+    }
+
+    #[test]
+    fn load_geth_tables() {
+        let dir = std::env::temp_dir().join(format!("akula-tools-systemcontracts-{}", std::process::id()));
+        fs::create_dir_all(dir.join("mirror/chapel")).unwrap();
+        fs::create_dir_all(dir.join("mirror/mainnet")).unwrap();
+        fs::write(dir.join("mirror/chapel/TokenHubContract"), "6004").unwrap();
+        fs::write(dir.join("mirror/mainnet/TokenHubContract"), "6005").unwrap();
+
+        let registry = SystemContractRegistry::load(&dir, "chapel").unwrap();
+        assert_eq!(Some(&Bytes::from_static(&[0x60, 0x04])), registry.upgrades["mirrorsync"].get("TokenHubContract"));
+        assert_eq!(1, registry.upgrades.len());
+
+        fs::write(dir.join("mirror/chapel/README"), "").unwrap();
+        let err = SystemContractRegistry::load(&dir, "chapel").unwrap_err();
+        assert_eq!("unknown system contract README", err.root_cause().to_string());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn parse_tables() {
+        for (network, _) in EMBEDDED_TABLES {
+            assert_eq!(network, SystemContractRegistry::embedded(network).unwrap().network);
+        }
+        assert!(SystemContractRegistry::embedded("ropsten").is_err());
+
+        let table = "# chapel\n\nniels ValidatorContract 0x6002\n  mirrorsync TokenHubContract 6004\n";
+        let registry = SystemContractRegistry::parse("chapel", table).unwrap();
+        assert_eq!(Some(&Bytes::from_static(&[0x60, 0x02])), registry.upgrades["niels"].get("ValidatorContract"));
+        assert_eq!(Some(&Bytes::from_static(&[0x60, 0x04])), registry.upgrades["mirrorsync"].get("TokenHubContract"));
+        let registry = SystemContractRegistry::parse("chapel", "planck StakeHubContract 6007\nhertz ValidatorContract 6008").unwrap();
+        assert_eq!(Some(&Bytes::from_static(&[0x60, 0x07])), registry.upgrades["planck"].get("StakeHubContract"));
+        assert_eq!(Some(&Bytes::from_static(&[0x60, 0x08])), registry.upgrades["hertz"].get("ValidatorContract"));
+
+        let err = SystemContractRegistry::parse("chapel", "niels ValidatorContract\n").unwrap_err();
+        assert_eq!("line 1: expected <fork> <contract> <hex bytecode>", err.to_string());
+        let err = SystemContractRegistry::parse("chapel", "#\nluban ValidatorContract 60\n").unwrap_err();
+        assert_eq!("line 2: luban does not upgrade system contracts", err.to_string());
+    }
+}
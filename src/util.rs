@@ -48,4 +48,238 @@ pub mod duration_as_millis {
     {
         serializer.serialize_u64(d.as_millis() as u64)
     }
-}
\ No newline at end of file
+}
+
+pub mod hexu64 {
+    use super::*;
+    use serde::Serializer;
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+        where
+            D: de::Deserializer<'de>,
+            T: From<u64>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        Ok(u64::from_str_radix(s.strip_prefix("0x").unwrap_or(&s), 16)
+            .map_err(D::Error::custom)?
+            .into())
+    }
+
+    pub fn serialize<S, T>(v: &T, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+            T: Copy + Into<u64>,
+    {
+        serializer.serialize_str(&format!("{:#x}", (*v).into()))
+    }
+
+    /// Optional hex numbers; the field also needs `#[serde(default)]`.
+    pub mod option {
+        use super::*;
+
+        pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+            where
+                D: de::Deserializer<'de>,
+                T: From<u64>,
+        {
+            match Option::<String>::deserialize(deserializer)? {
+                Some(s) => Ok(Some(
+                    u64::from_str_radix(s.strip_prefix("0x").unwrap_or(&s), 16)
+                        .map_err(D::Error::custom)?
+                        .into(),
+                )),
+                None => Ok(None),
+            }
+        }
+
+        pub fn serialize<S, T>(v: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+                T: Copy + Into<u64>,
+        {
+            match v {
+                Some(v) => super::serialize(v, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+    }
+}
+
+/// Numbers as geth and the tools around it write them: JSON or TOML integers, `0x` hex
+/// strings or decimal strings, up to 256 bits, each read into the type of the field and
+/// rejected with the value and the type if it does not fit. Values are written as the type
+/// itself writes them.
+pub mod numeric {
+    use super::*;
+    use crate::models::chainspec::BlockNumber;
+    use ethereum_types::U256;
+    use serde::{Serialize, Serializer};
+    use std::fmt;
+
+    pub trait Numeric: Sized + Serialize {
+        const NAME: &'static str;
+
+        fn from_u256(v: U256) -> Option<Self>;
+    }
+
+    impl Numeric for u64 {
+        const NAME: &'static str = "u64";
+
+        fn from_u256(v: U256) -> Option<Self> {
+            (v <= U256::from(u64::MAX)).then(|| v.as_u64())
+        }
+    }
+
+    impl Numeric for BlockNumber {
+        const NAME: &'static str = "BlockNumber";
+
+        fn from_u256(v: U256) -> Option<Self> {
+            u64::from_u256(v).map(BlockNumber)
+        }
+    }
+
+    impl Numeric for U256 {
+        const NAME: &'static str = "U256";
+
+        fn from_u256(v: U256) -> Option<Self> {
+            Some(v)
+        }
+    }
+
+    enum ParseError {
+        Invalid,
+        Overflow,
+    }
+
+    /// Key of the map serde_json with `arbitrary_precision` hands a number over as, its text
+    /// being the value.
+    const JSON_NUMBER_TOKEN: &str = "$serde_json::private::Number";
+
+    /// Parses a `0x` hex or a decimal string, overflowing past 256 bits.
+    fn parse(s: &str) -> Result<U256, ParseError> {
+        let (digits, radix) = match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+            Some(digits) => (digits, 16),
+            None => (s, 10),
+        };
+        if digits.is_empty() {
+            return Err(ParseError::Invalid);
+        }
+        let mut v = U256::zero();
+        for c in digits.chars() {
+            let digit = c.to_digit(radix).ok_or(ParseError::Invalid)?;
+            v = v
+                .checked_mul(U256::from(radix))
+                .and_then(|v| v.checked_add(U256::from(digit)))
+                .ok_or(ParseError::Overflow)?;
+        }
+        Ok(v)
+    }
+
+    struct NumericVisitor<T>(std::marker::PhantomData<T>);
+
+    impl<T: Numeric> NumericVisitor<T> {
+        fn convert<E: Error>(v: U256, shown: &dyn fmt::Display) -> Result<T, E> {
+            T::from_u256(v).ok_or_else(|| E::custom(format!("{} overflows {}", shown, T::NAME)))
+        }
+    }
+
+    impl<'de, T: Numeric> de::Visitor<'de> for NumericVisitor<T> {
+        type Value = T;
+
+        fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            write!(f, "a {} as an integer, a 0x hex string or a decimal string", T::NAME)
+        }
+
+        fn visit_u64<E: Error>(self, v: u64) -> Result<T, E> {
+            Self::convert(U256::from(v), &v)
+        }
+
+        fn visit_i64<E: Error>(self, v: i64) -> Result<T, E> {
+            if v < 0 {
+                return Err(E::custom(format!("{} is negative, not a {}", v, T::NAME)));
+            }
+            Self::convert(U256::from(v), &v)
+        }
+
+        fn visit_u128<E: Error>(self, v: u128) -> Result<T, E> {
+            Self::convert(U256::from(v), &v)
+        }
+
+        fn visit_f64<E: Error>(self, v: f64) -> Result<T, E> {
+            // TOML integers past 64 bits reach here rounded, write them as strings to keep them
+            Err(E::custom(format!("{} is not an exact integer, write it as a hex or decimal string", v)))
+        }
+
+        fn visit_map<A: de::MapAccess<'de>>(self, mut map: A) -> Result<T, A::Error> {
+            if map.next_key::<String>()?.as_deref() != Some(JSON_NUMBER_TOKEN) {
+                return Err(A::Error::invalid_type(de::Unexpected::Map, &self));
+            }
+            let text = map.next_value::<String>()?;
+            if text.starts_with('-') {
+                return Err(A::Error::custom(format!("{} is negative, not a {}", text, T::NAME)));
+            }
+            match parse(&text) {
+                Ok(n) => Self::convert(n, &text),
+                Err(ParseError::Overflow) => Err(A::Error::custom(format!("{} overflows {}", text, T::NAME))),
+                Err(ParseError::Invalid) => Err(A::Error::custom(format!(
+                    "{} is not an exact integer, write it as a hex or decimal string",
+                    text
+                ))),
+            }
+        }
+
+        fn visit_str<E: Error>(self, v: &str) -> Result<T, E> {
+            match parse(v) {
+                Ok(n) => Self::convert(n, &format_args!("{:?}", v)),
+                Err(ParseError::Overflow) => Err(E::custom(format!("{:?} overflows {}", v, T::NAME))),
+                Err(ParseError::Invalid) => Err(E::invalid_value(de::Unexpected::Str(v), &self)),
+            }
+        }
+    }
+
+    pub fn deserialize<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+        where
+            D: de::Deserializer<'de>,
+            T: Numeric,
+    {
+        deserializer.deserialize_any(NumericVisitor(std::marker::PhantomData))
+    }
+
+    pub fn serialize<S, T>(v: &T, serializer: S) -> Result<S::Ok, S::Error>
+        where
+            S: Serializer,
+            T: Numeric,
+    {
+        v.serialize(serializer)
+    }
+
+    /// Optional numbers, `null` being none; the field also needs `#[serde(default)]`.
+    pub mod option {
+        use super::*;
+
+        struct Wrapper<T>(T);
+
+        impl<'de, T: Numeric> Deserialize<'de> for Wrapper<T> {
+            fn deserialize<D: de::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                super::deserialize(deserializer).map(Wrapper)
+            }
+        }
+
+        pub fn deserialize<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+            where
+                D: de::Deserializer<'de>,
+                T: Numeric,
+        {
+            Ok(Option::<Wrapper<T>>::deserialize(deserializer)?.map(|Wrapper(v)| v))
+        }
+
+        pub fn serialize<S, T>(v: &Option<T>, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: Serializer,
+                T: Numeric,
+        {
+            v.serialize(serializer)
+        }
+    }
+}